@@ -0,0 +1,220 @@
+use crate::user_input;
+use crate::encounter;
+use crate::encounter::{Character, Condition};
+use crate::stat_search;
+use crate::party;
+use crate::log;
+use crate::log::EventKind;
+use titlecase::titlecase;
+use rand::Rng;
+
+///
+/// Rolls a skill or ability check for a character, using their statblock for monsters and asking for the roll from players
+///
+pub fn roll_check(character: &Character, check: &str) -> (String, i32) {
+    match stat_search::check_bonus(&character.character_type, check) {
+        Some(modifier) => {
+            let roll = rand::thread_rng().gen_range(1..21);
+            (format!("rolled a {}{:+} = {}", roll, modifier, roll + modifier), roll + modifier)
+        },
+        None => {
+            println!("\nEnter {}/{}'s {} check:", character.character_type, character.name, titlecase(check));
+            let total = user_input::int_input();
+            (format!("rolled a {}", total), total)
+        }
+    }
+}
+
+///
+/// Rolls whichever of two checks gives a character the better result, as defenders do against grapples and shoves
+///
+fn roll_best_check(character: &Character, first: &str, second: &str) -> (String, i32) {
+    match (stat_search::check_bonus(&character.character_type, first), stat_search::check_bonus(&character.character_type, second)) {
+        (Some(first_bonus), Some(second_bonus)) => {
+            let (check, modifier) = if first_bonus >= second_bonus {(first, first_bonus)} else {(second, second_bonus)};
+            let roll = rand::thread_rng().gen_range(1..21);
+            (format!("rolled {} {}{:+} = {}", titlecase(check), roll, modifier, roll + modifier), roll + modifier)
+        },
+        _ => {
+            println!("\nEnter {}/{}'s {} or {} check:", character.character_type, character.name, titlecase(first), titlecase(second));
+            let total = user_input::int_input();
+            (format!("rolled a {}", total), total)
+        }
+    }
+}
+
+///
+/// Asks for a skill or ability name until a valid one is entered
+///
+fn check_input() -> String {
+    let check = user_input::input();
+    if stat_search::is_check(&check) {
+        check
+    } else {
+        println!("\nInvalid check! Enter a skill (e.g. athletics) or an ability (e.g. dex):");
+        check_input()
+    }
+}
+
+///
+/// Asks for a creature by number, name or #ID, returning None if the user backs out
+///
+fn creature_input(characters: &[Character], prompt: &str) -> Option<usize> {
+    println!("{}", prompt);
+    encounter::creature_input(characters)
+}
+
+///
+/// Rolls the same check for one or more creatures
+///
+fn group_check(characters: &[Character]) -> Vec<String> {
+    println!("Choose or enter the numbers, names or #IDs of the creatures rolling, separated by commas:");
+    let indexes = encounter::creature_list_input(characters);
+    println!("\nEnter the skill or ability to check (e.g. athletics, dex):");
+    let check = check_input();
+
+    let mut results = Vec::new();
+    for index in indexes {
+        let character = &characters[index];
+        let (roll_string, _) = roll_check(character, &check);
+        results.push(format!("{}/{} {} on {}", character.character_type, character.name, roll_string, titlecase(&check)));
+    }
+    results
+}
+
+///
+/// Resolves a contested check between two creatures, optionally applying a condition to the loser for grapples and shoves
+///
+fn contest(characters: &mut [Character]) -> Vec<String> {
+    println!("Is this a (g)rapple, a (s)hove, or some (o)ther contest?");
+    let kind = user_input::input();
+    println!();
+    let attacker = match creature_input(characters, "Choose or enter the number, name or #ID of the creature starting the contest:") {
+        Some(attacker) => attacker,
+        None => return Vec::new(),
+    };
+    println!();
+    let defender = match creature_input(characters, "Choose or enter the number, name or #ID of the creature resisting:") {
+        Some(defender) => defender,
+        None => return Vec::new(),
+    };
+
+    // Grapples and shoves are Athletics against the defender's choice of Athletics or Acrobatics
+    let (attack_roll, defend_roll) = match kind.as_str() {
+        "g" | "s" => (roll_check(&characters[attacker], "athletics"), roll_best_check(&characters[defender], "athletics", "acrobatics")),
+        _ => {
+            println!("\nEnter the skill or ability {} is using:", characters[attacker].name);
+            let attacker_check = check_input();
+            println!("\nEnter the skill or ability {} is using:", characters[defender].name);
+            let defender_check = check_input();
+            (roll_check(&characters[attacker], &attacker_check), roll_check(&characters[defender], &defender_check))
+        }
+    };
+
+    let attacker_label = format!("{}/{}", characters[attacker].character_type, characters[attacker].name);
+    let defender_label = format!("{}/{}", characters[defender].character_type, characters[defender].name);
+    let mut results = vec![
+        format!("{} {}", attacker_label, attack_roll.0),
+        format!("{} {}", defender_label, defend_roll.0),
+    ];
+
+    // Ties leave the situation unchanged, so the contest's starter has to beat the resister
+    if attack_roll.1 > defend_roll.1 {
+        results.push(format!("{} wins the contest", attacker_label));
+        let condition = match kind.as_str() {
+            "g" => Some("Grappled"),
+            "s" => Some("Prone"),
+            _ => None,
+        };
+        if let Some(condition) = condition {
+            println!("\n{} wins! Apply {} to {}? (y/n)", attacker_label, condition, defender_label);
+            if user_input::input() == "y" {
+                characters[defender].conditions.push(Condition {
+                    name: condition.to_string(),
+                    spell: None,
+                    caster: None,
+                    caster_id: None,
+                });
+                results.push(format!("{} is now {}", defender_label, condition));
+            }
+        }
+    } else {
+        results.push(format!("{} wins the contest", defender_label));
+    }
+    results
+}
+
+///
+/// Rolls a creature's Stealth against the passive Perception of everyone else in the encounter
+///
+fn passive_perception(characters: &[Character]) -> Vec<String> {
+    let sneaker = match creature_input(characters, "Choose or enter the number, name or #ID of the creature hiding:") {
+        Some(sneaker) => sneaker,
+        None => return Vec::new(),
+    };
+    let (roll_string, stealth) = roll_check(&characters[sneaker], "stealth");
+    let mut results = vec![format!("{}/{} {} on Stealth", characters[sneaker].character_type, characters[sneaker].name, roll_string)];
+
+    for (index, character) in characters.iter().enumerate() {
+        if index == sneaker {
+            continue;
+        }
+        // Players in the party roster already have their passive Perception on file
+        let member = if character.character_type == "Player" {party::find_member(&character.name)} else {None};
+        let passive = match (stat_search::check_bonus(&character.character_type, "perception"), member) {
+            (Some(modifier), _) => 10 + modifier,
+            (None, Some(member)) => member.passive_perception,
+            (None, None) => {
+                println!("\nEnter {}/{}'s passive Perception:", character.character_type, character.name);
+                user_input::int_input()
+            }
+        };
+        let outcome = if passive >= stealth {"spots them"} else {"doesn't notice them"};
+        results.push(format!("{}/{} (passive Perception {}) {}", character.character_type, character.name, passive, outcome));
+    }
+    results
+}
+
+///
+/// Menu used for ad-hoc ability checks, contested rolls and passive Perception comparisons
+///
+pub fn checks() {
+    let mut characters = encounter::load_encounter_file();
+
+    // Stores the last set of results so they survive the screen clear
+    let mut results: Vec<String> = Vec::new();
+
+    loop {
+        encounter::print_creatures(&characters);
+        for result in &results {
+            println!("{}", result);
+        }
+        if !results.is_empty() {
+            println!();
+        }
+
+        println!("Roll a (c)heck, a contest between (t)wo creatures, or compare (p)assive Perception, or type \"done\" to return:");
+        let input = user_input::input();
+        println!();
+        if encounter::input_break_check(&input) == 0 {
+            break;
+        }
+
+        match input.as_str() {
+            "c" => {results = group_check(&characters);},
+            "t" => {
+                results = contest(&mut characters);
+                encounter::save_encounter_file(&mut characters);
+            },
+            "p" => {results = passive_perception(&characters);},
+            _ => {
+                results = vec!["Invalid command!".to_string()];
+                continue;
+            }
+        }
+
+        for result in &results {
+            log::record(EventKind::Roll, result, None);
+        }
+    }
+}
@@ -0,0 +1,545 @@
+use crate::encounter;
+use crate::encounter::Character;
+use crate::stat_search;
+use crate::dice;
+use crate::history;
+use crate::turn;
+use crate::log;
+use crate::conditions;
+use crate::log::EventKind;
+use crate::tui;
+use crate::export;
+use crate::export::Format;
+use crate::validation;
+use crate::web;
+use crate::web::HpMode;
+use crate::rpc;
+use crate::import;
+use crate::party;
+use crate::initiative;
+use crate::surprise;
+use titlecase::titlecase;
+
+///
+/// Gets the value following a flag such as --count, if it was given
+///
+fn option_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter().position(|arg| arg == flag).and_then(|i| args.get(i + 1)).map(|value| value.as_str())
+}
+
+///
+/// Gets the arguments that aren't flags or flag values
+///
+fn positional(args: &[String]) -> Vec<&str> {
+    let mut values = Vec::new();
+    let mut skip = false;
+    for arg in args {
+        if skip {
+            skip = false;
+        } else if arg.starts_with("--") {
+            // Boolean flags don't take a value
+            skip = !["--json", "--player", "--lan", "--hide-hp", "--add", "--roll"].contains(&arg.as_str());
+        } else {
+            values.push(arg.as_str());
+        }
+    }
+    values
+}
+
+///
+/// Finds a creature by its number or ID as shown by the show command, or by name, prefix or type/name
+///
+fn creature_number(arg: Option<&&str>, characters: &[Character]) -> Result<usize, String> {
+    let token = arg.ok_or("Enter a creature number or name".to_string())?;
+    let matches = encounter::find_creatures(token, characters);
+    match matches.len() {
+        0 => Err(format!("No creature matches \"{}\"", token)),
+        1 => Ok(matches[0]),
+        _ => {
+            let names: Vec<String> = matches.iter().map(|&i| format!("{}/{} (#{})", characters[i].character_type, characters[i].name, characters[i].id)).collect();
+            Err(format!("\"{}\" matches more than one creature: {}. Use an ID like #{} to pick one", token, names.join(", "), characters[matches[0]].id))
+        }
+    }
+}
+
+///
+/// Builds the error for a monster that isn't in the statblock library, suggesting close matches
+///
+fn unknown_monster(monster_type: &str) -> String {
+    let suggestions = stat_search::suggest_monsters(monster_type);
+    if suggestions.is_empty() {
+        format!("Unknown monster: {}", monster_type)
+    } else {
+        format!("Unknown monster: {}. Did you mean {}?", monster_type, suggestions.join(", "))
+    }
+}
+
+///
+/// Adds one or more monsters: add <monster> [--count N] [--name NAME]
+///
+fn add(args: &[String]) -> Result<(), String> {
+    let monster_type = positional(args).join(" ");
+    if monster_type.is_empty() {
+        return Err("Usage: add <monster> [--count N] [--name NAME]".to_string());
+    }
+    let count: usize = match option_value(args, "--count") {
+        Some(count) => count.parse().map_err(|_| "--count must be a whole number".to_string())?,
+        None => 1,
+    };
+    if count > encounter::MAX_COUNT as usize {
+        return Err(format!("--count can't be more than {}", encounter::MAX_COUNT));
+    }
+
+    let mut characters = encounter::load_encounter_file();
+    let base = titlecase(option_value(args, "--name").unwrap_or(&monster_type));
+    let mut added = Vec::new();
+    for _ in 0..count {
+        let name = encounter::unique_name(&base, &characters, count > 1);
+        let character = stat_search::create_monster(&monster_type, name).ok_or_else(|| unknown_monster(&monster_type))?;
+        added.push(format!("Added {}/{} with initiative {}", character.character_type, character.name, character.initiative));
+        characters.push(character);
+    }
+    encounter::save_added(&mut characters, &added);
+    for message in &added {
+        println!("{}", message);
+    }
+    Ok(())
+}
+
+///
+/// Adds a player: add-player <name> <ac> <initiative> [--level N]
+///
+fn add_player(args: &[String]) -> Result<(), String> {
+    let values = positional(args);
+    let usage = "Usage: add-player <name> <ac> <initiative> [--level N]".to_string();
+    if values.len() != 3 {
+        return Err(usage);
+    }
+    let ac: i32 = values[1].parse().map_err(|_| usage.clone())?;
+    let initiative: i32 = values[2].parse().map_err(|_| usage.clone())?;
+    let level: i32 = option_value(args, "--level").unwrap_or("0").parse().map_err(|_| usage.clone())?;
+
+    let mut characters = encounter::load_encounter_file();
+    let name = titlecase(values[0]);
+    // Players in the party roster get their real HP
+    let hp = party::find_member(&name).map(|member| member.max_hp).unwrap_or(encounter::PLAYER_HP);
+    println!("Added Player/{} with initiative {}", name, initiative);
+    let character = Character {
+        id: 0,
+        name,
+        character_type: "Player".to_string(),
+        ac,
+        hp,
+        max_hp: hp,
+        initiative,
+        level,
+        concentration: None,
+        conditions: Vec::new(),
+        surprised: false,
+        hidden: false,
+        joins_round: None,
+    };
+    let added = format!("Added Player/{} with initiative {}", character.name, initiative);
+    characters.push(character);
+    encounter::save_added(&mut characters, &[added]);
+    Ok(())
+}
+
+///
+/// Adds everyone in the party roster who isn't already in the encounter: add-party [--roll] [name=initiative...]
+///
+fn add_party(args: &[String]) -> Result<(), String> {
+    let party = party::read_party()?;
+    if party.is_empty() {
+        return Err("The party roster is empty. Add players to it from the interactive tracker".to_string());
+    }
+    let mut rolls = Vec::new();
+    for value in positional(args) {
+        let (name, initiative) = value.split_once('=').ok_or("Give initiative rolls as name=initiative".to_string())?;
+        let initiative: i32 = initiative.parse().map_err(|_| format!("{}'s initiative must be a whole number", name))?;
+        let member = party::find_member(name).ok_or(format!("{} isn't in the party roster", name))?;
+        rolls.push((member.name, initiative));
+    }
+    let roll = args.iter().any(|arg| arg == "--roll");
+
+    let mut characters = encounter::load_encounter_file();
+    let members: Vec<&party::PartyMember> = party.iter()
+        .filter(|member| !characters.iter().any(|c| c.character_type == "Player" && c.name.to_lowercase() == member.name.to_lowercase()))
+        .collect();
+    let missing: Vec<&str> = members.iter().filter(|member| !rolls.iter().any(|(name, _)| *name == member.name)).map(|member| member.name.as_str()).collect();
+    if !roll && !missing.is_empty() {
+        return Err(format!("No initiative given for {}. Add name=initiative for each, or --roll to roll the rest", missing.join(", ")));
+    }
+
+    let mut added = Vec::new();
+    for member in members {
+        let initiative = rolls.iter().find(|(name, _)| *name == member.name).map(|(_, initiative)| *initiative).unwrap_or_else(|| party::roll_initiative(member));
+        println!("Added Player/{} with initiative {}", member.name, initiative);
+        added.push(format!("Added Player/{} with initiative {}", member.name, initiative));
+        characters.push(party::to_character(member, initiative));
+    }
+    encounter::save_added(&mut characters, &added);
+    Ok(())
+}
+
+///
+/// Starts combat, rerolling monster initiative and setting player initiative: start [--roll] [name=initiative...]
+///
+fn start(args: &[String]) -> Result<(), String> {
+    let mut characters = encounter::load_encounter_file();
+    if characters.is_empty() {
+        return Err("The encounter is empty".to_string());
+    }
+    let mut rolls = Vec::new();
+    for value in positional(args) {
+        let (token, initiative) = value.split_once('=').ok_or("Give initiative rolls as name=initiative".to_string())?;
+        let index = creature_number(Some(&token), &characters)?;
+        let initiative: i32 = initiative.parse().map_err(|_| format!("{}'s initiative must be a whole number", token))?;
+        if characters[index].character_type != "Player" {
+            return Err(format!("{}/{} is a monster, so its initiative is rolled", characters[index].character_type, characters[index].name));
+        }
+        rolls.push((index, initiative));
+    }
+    let roll = args.iter().any(|arg| arg == "--roll");
+
+    // Every player needs a roll, either given or rolled from the party roster
+    let mut lines = initiative::roll_monsters(&mut characters);
+    let players: Vec<usize> = (0..characters.len()).filter(|&i| characters[i].character_type == "Player").collect();
+    for index in players {
+        let given = rolls.iter().find(|(i, _)| *i == index).map(|(_, initiative)| *initiative);
+        let initiative = match (given, roll) {
+            (Some(initiative), _) => initiative,
+            (None, true) => initiative::roll_player(&characters[index]).ok_or(format!("{} isn't in the party roster, so give their roll as {}=initiative", characters[index].name, characters[index].name.to_lowercase()))?,
+            (None, false) => return Err(format!("No initiative given for {}. Add name=initiative for each player, or --roll to roll from the party roster", characters[index].name)),
+        };
+        characters[index].initiative = initiative;
+        lines.push(format!("Player/{} rolled {}", characters[index].name, initiative));
+    }
+    lines.extend(initiative::finish(&mut characters));
+    for line in lines {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+///
+/// Damages or heals a creature: damage <creature> <amount> or heal <creature> <amount>
+///
+fn damage(args: &[String], direction: i32) -> Result<(), String> {
+    let values = positional(args);
+    let mut characters = encounter::load_encounter_file();
+    let index = creature_number(values.first(), &characters)?;
+    let amount: i32 = values.get(1).and_then(|amount| amount.parse().ok()).ok_or("Enter a whole number amount".to_string())?;
+
+    let result = encounter::apply_damage(&mut characters, index, amount * direction, None);
+    println!("{}/{} is now at {} HP", characters[index].character_type, characters[index].name, characters[index].hp);
+    if let Some(result) = result {
+        println!("{}", result);
+    }
+    encounter::save_encounter_file(&mut characters);
+    Ok(())
+}
+
+///
+/// Removes a creature: remove <creature>
+///
+fn remove(args: &[String]) -> Result<(), String> {
+    let values = positional(args);
+    let mut characters = encounter::load_encounter_file();
+    let index = creature_number(values.first(), &characters)?;
+
+    conditions::drop_concentration(&mut characters, index);
+    let character = characters.remove(index);
+    println!("Removed {}/{}", character.character_type, character.name);
+    log::record_for(EventKind::Remove, &format!("Removed {}/{}", character.character_type, character.name), None, &[character.id]);
+    encounter::save_encounter_file(&mut characters);
+    Ok(())
+}
+
+///
+/// Marks a creature as surprised, hidden or joining later: mark <creature> <surprised|unsurprised|hidden|revealed|round=N>
+///
+fn mark(args: &[String]) -> Result<(), String> {
+    let values = positional(args);
+    let usage = "Usage: mark <creature> <surprised|unsurprised|hidden|revealed|round=N>".to_string();
+    if values.len() != 2 {
+        return Err(usage);
+    }
+    let mut characters = encounter::load_encounter_file();
+    let index = creature_number(values.first(), &characters)?;
+    let message = match values[1] {
+        "surprised" | "unsurprised" => surprise::set_surprised(&mut characters, index, values[1] == "surprised"),
+        "hidden" => surprise::hide(&mut characters, index),
+        "revealed" => encounter::reveal(&mut characters, index).ok_or(format!("{}/{} isn't hidden", characters[index].character_type, characters[index].name))?,
+        mark => {
+            let joins = mark.strip_prefix("round=").ok_or(usage)?;
+            let joins: usize = joins.parse().map_err(|_| "The round must be a whole number".to_string())?;
+            surprise::set_joins_round(&mut characters, index, joins, turn::load_turn().round)
+        }
+    };
+    println!("{}", message);
+    encounter::save_encounter_file(&mut characters);
+    Ok(())
+}
+
+///
+/// Moves the turn forwards or backwards and reports whose turn it is
+///
+fn advance(forward: bool) -> Result<(), String> {
+    let (_, lines) = turn::take_turn(forward).ok_or("The encounter is empty".to_string())?;
+    for line in &lines {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+///
+/// Prints the encounter in turn order with each creature's ID, or as JSON with --json. --player hides monster stats
+///
+fn show(args: &[String]) -> Result<(), String> {
+    let characters = encounter::load_encounter_file();
+    let state = turn::load_turn();
+    let player = args.iter().any(|arg| arg == "--player");
+    if args.iter().any(|arg| arg == "--json") {
+        let creatures = if player {
+            characters.iter().filter(|creature| encounter::shown_to_players(creature, state.round)).map(|creature| serde_json::json!({
+                "name": creature.name,
+                "player": creature.character_type == "Player",
+                "health": if creature.character_type == "Player" {None} else {Some(encounter::health_band(creature))},
+                "conditions": creature.conditions.iter().map(|c| c.name.clone()).collect::<Vec<String>>(),
+            })).collect()
+        } else {
+            serde_json::to_value(&characters).unwrap()
+        };
+        let json = serde_json::json!({
+            "round": state.round,
+            "position": state.position,
+            "creatures": creatures,
+        });
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+        return Ok(());
+    }
+
+    println!("Round {}", state.round);
+    for (index, creature) in characters.iter().enumerate() {
+        // Players don't see hidden creatures or reinforcements that haven't arrived
+        if player && !encounter::shown_to_players(creature, state.round) {
+            continue;
+        }
+        let marker = if index + 1 == state.position {"-->"} else {"   "};
+        // The player view leaves out stats, only describing how hurt monsters look
+        let mut line = if player {
+            match creature.character_type.as_str() {
+                "Player" => format!("{} {}. {}", marker, index + 1, creature.name),
+                _ => format!("{} {}. {} ({})", marker, index + 1, creature.name, encounter::health_band(creature)),
+            }
+        } else if creature.character_type == "Player" && encounter::tracks_player_hp(creature.max_hp) {
+            format!("{} {}. {} - Player/{} (#{}), AC: {}, HP: {}", marker, index + 1, creature.initiative, creature.name, creature.id, creature.ac, creature.hp)
+        } else if creature.character_type == "Player" {
+            format!("{} {}. {} - Player/{} (#{}), AC: {}", marker, index + 1, creature.initiative, creature.name, creature.id, creature.ac)
+        } else {
+            format!("{} {}. {} - {}/{} (#{}), AC: {}, HP: {}", marker, index + 1, creature.initiative, creature.character_type, creature.name, creature.id, creature.ac, creature.hp)
+        };
+        let mut effects = if player {Vec::new()} else {encounter::status_tags(creature, state.round)};
+        if let Some(spell) = &creature.concentration {
+            effects.push(format!("Concentrating: {}", spell));
+        }
+        effects.extend(creature.conditions.iter().map(|c| c.name.clone()));
+        if !effects.is_empty() {
+            line.push_str(&format!(" [{}]", effects.join(", ")));
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+///
+/// Rolls a dice expression: roll <dice>
+///
+fn roll(args: &[String]) -> Result<(), String> {
+    let expression = positional(args).join("");
+    let (_, breakdown) = dice::roll(&expression).ok_or(format!("Couldn't roll \"{}\", try something like 2d6+3", expression))?;
+    println!("{}", breakdown);
+    log::record(EventKind::Roll, &format!("Rolled {}: {}", expression, breakdown), None);
+    Ok(())
+}
+
+///
+/// Exports the encounter or a single statblock: export <md|html|text> [--monster NAME] [--output FILE] [--player]
+///
+fn export(args: &[String]) -> Result<(), String> {
+    let usage = "Usage: export <md|html|text> [--monster NAME] [--output FILE] [--player]".to_string();
+    let format = positional(args).first().and_then(|format| Format::parse(format)).ok_or(usage)?;
+    let document = match option_value(args, "--monster") {
+        Some(monster) => export::statblock_document(monster, format).ok_or_else(|| unknown_monster(monster))?,
+        None => export::encounter_document(format, args.iter().any(|arg| arg == "--player")),
+    };
+    match option_value(args, "--output") {
+        Some(output) => {
+            let path = shellexpand::tilde(output).into_owned();
+            std::fs::write(&path, document).map_err(|e| format!("Couldn't write {}: {}", path, e))?;
+            println!("Exported to {}", path);
+        },
+        None => print!("{}", document),
+    }
+    Ok(())
+}
+
+///
+/// Serves the player dashboard until interrupted: dashboard [--port N] [--lan | --bind ADDR] [--hide-hp]
+///
+fn dashboard(args: &[String]) -> Result<(), String> {
+    let port: u16 = match option_value(args, "--port") {
+        Some(port) => port.parse().map_err(|_| "--port must be a port number".to_string())?,
+        None => web::DEFAULT_PORT,
+    };
+    let lan = args.iter().any(|arg| arg == "--lan");
+    let hp_mode = if args.iter().any(|arg| arg == "--hide-hp") {HpMode::Hidden} else {HpMode::Bands};
+
+    let address = web::address(option_value(args, "--bind"), lan, port)?;
+    let listener = std::net::TcpListener::bind(address).map_err(|e| format!("Couldn't listen on {}: {}", address, e))?;
+    println!("Serving the dashboard at http://{}. Press Ctrl-C to stop", address);
+    web::serve(listener, hp_mode);
+    Ok(())
+}
+
+///
+/// Controls the tracker with JSON-RPC over stdin and stdout, or over a Unix socket: rpc [--socket PATH]
+///
+fn serve_rpc(args: &[String]) -> Result<(), String> {
+    match option_value(args, "--socket") {
+        Some(path) => {
+            let path = shellexpand::tilde(path).into_owned();
+            rpc::serve_socket(&path).map_err(|e| format!("Couldn't listen on {}: {}", path, e))
+        },
+        None => {
+            rpc::serve_stdio();
+            Ok(())
+        }
+    }
+}
+
+///
+/// Imports Foundry VTT and Roll20 exports into the statblock library and encounter: import <file...> [--add]
+///
+fn import_exports(args: &[String]) -> Result<(), String> {
+    let paths: Vec<String> = positional(args).iter().map(|path| path.to_string()).collect();
+    if paths.is_empty() {
+        return Err("Usage: import <file...> [--add]".to_string());
+    }
+    let mut import = import::read_exports(&paths, args.iter().any(|arg| arg == "--add"))?;
+    let done = import::apply(&mut import);
+    import::print_report(&done, &import.unsupported);
+    Ok(())
+}
+
+///
+/// Checks statblock files and reports every problem: validate [file...]
+///
+fn validate(args: &[String]) -> Result<(), String> {
+    let problems = if args.is_empty() {
+        validation::check_library()
+    } else {
+        let mut problems = Vec::new();
+        for path in args {
+            let path = shellexpand::tilde(path).into_owned();
+            match validation::check_file(&path) {
+                Some((creatures, file_problems)) => {
+                    println!("{}: {} valid creatures", path, creatures.len());
+                    problems.extend(file_problems);
+                },
+                None => return Err(format!("Couldn't read {}", path)),
+            }
+        }
+        problems
+    };
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    if problems.is_empty() {
+        println!("No problems found");
+        Ok(())
+    } else {
+        Err(format!("Found {} problems", problems.len()))
+    }
+}
+
+///
+/// Prints usage for the non-interactive commands
+///
+fn print_help() {
+    println!("Usage: dnd-encounter-tracker [command]
+
+Runs the interactive tracker when no command is given. A <creature> is its number or #ID from show,
+its name, or a type/name pair like orc/grug.
+
+Commands:
+add <monster> [--count N] [--name NAME]       add monsters from the statblock library
+add-player <name> <ac> <initiative> [--level N] add a player character
+add-party [--roll] [name=initiative...]       add the party roster, rolling initiative with --roll
+start [--roll] [name=initiative...]           start combat, rolling monsters and setting player initiative
+damage <creature> <amount>                    damage a creature
+heal <creature> <amount>                      heal a creature
+remove <creature>                             remove a creature
+mark <creature> <surprised|unsurprised|hidden|revealed|round=N>
+                                              surprise or hide a creature, or hold it back until a round
+next                                          move to the next turn
+prev                                          move to the previous turn
+show [--json] [--player]                      print the encounter in turn order
+roll <dice>                                   roll dice, e.g. 2d6+3
+validate [file...]                            check statblock files for invalid creatures
+import <file...> [--add]                      import Foundry VTT or Roll20 exports, adding every actor with --add
+export <md|html|text> [--monster NAME] [--output FILE] [--player]
+                                              export the encounter or a statblock
+tui                                           open the full-screen interface
+dashboard [--port N] [--lan | --bind ADDR] [--hide-hp]
+                                              serve the initiative order to a second screen, on this
+                                              computer's local network address with --lan, or on a
+                                              private or loopback ADDR
+rpc [--socket PATH]                           control the tracker with JSON-RPC over stdio or a socket
+undo                                          undo the last change
+redo                                          redo the last undone change
+help                                          show this message");
+}
+
+///
+/// Runs a single command against the saved encounter and returns the process exit code
+///
+pub fn run(args: &[String]) -> i32 {
+    let result = match args[0].as_str() {
+        "add" => add(&args[1..]),
+        "add-player" => add_player(&args[1..]),
+        "add-party" => add_party(&args[1..]),
+        "start" => start(&args[1..]),
+        "damage" => damage(&args[1..], 1),
+        "heal" => damage(&args[1..], -1),
+        "remove" => remove(&args[1..]),
+        "mark" => mark(&args[1..]),
+        "next" => advance(true),
+        "prev" | "previous" => advance(false),
+        "show" => show(&args[1..]),
+        "roll" => roll(&args[1..]),
+        "validate" => validate(&args[1..]),
+        "import" => import_exports(&args[1..]),
+        "export" => export(&args[1..]),
+        "dashboard" => dashboard(&args[1..]),
+        "rpc" => serve_rpc(&args[1..]),
+        "tui" => tui::run().map_err(|e| e.to_string()),
+        "undo" => history::undo(),
+        "redo" => history::redo(),
+        "help" | "--help" | "-h" => {print_help(); Ok(())},
+        _ => {
+            print_help();
+            Err(format!("Unknown command: {}", args[0]))
+        }
+    };
+
+    match result {
+        Ok(..) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::user_input;
+use crate::encounter;
+use crate::encounter::{Character, Condition};
+use crate::log;
+use crate::log::EventKind;
+use crate::render;
+use crate::stat_search;
+use titlecase::titlecase;
+use colored::*;
+
+///
+/// Returns the "type/name" label used to link conditions to the caster concentrating on them
+///
+pub fn caster_label(character: &Character) -> String {
+    format!("{}/{}", character.character_type, character.name)
+}
+
+///
+/// Ends a character's concentration and removes every condition linked to that spell from its targets.
+/// Conditions saved before casters had IDs are matched by the caster's label instead
+///
+pub fn drop_concentration(characters: &mut [Character], index: usize) {
+    let caster = caster_label(&characters[index]);
+    let caster_id = characters[index].id;
+    let spell = match characters[index].concentration.take() {
+        Some(spell) => spell,
+        None => return,
+    };
+
+    for character in characters.iter_mut() {
+        character.conditions.retain(|condition| {
+            let linked = match condition.caster_id {
+                Some(id) => id == caster_id,
+                None => condition.caster.as_deref() == Some(caster.as_str()),
+            };
+            !(linked && condition.spell.as_deref() == Some(spell.as_str()))
+        });
+    }
+}
+
+///
+/// Rolls a Constitution save for a concentrating character after taking damage, with a DC of max(10, damage/2)
+///
+pub fn concentration_check(characters: &mut [Character], index: usize, damage: i32, player_roll: Option<i32>) -> Option<String> {
+    let spell = characters[index].concentration.clone()?;
+    let dc = std::cmp::max(10, damage / 2);
+    let label = caster_label(&characters[index]);
+
+    // Monsters roll from their statblock, anyone without one is asked for their roll unless it was given up front
+    if stat_search::save_bonus(&characters[index].character_type, "con").is_none() && player_roll.is_none() {
+        println!("\n{} is concentrating on {}!", label, spell);
+    }
+    let (roll_string, total) = encounter::roll_save(&characters[index], "con", dc, player_roll);
+
+    if total >= dc {
+        Some(format!("{} {} against DC {}, and kept concentration on {}", label, roll_string, dc, spell))
+    } else {
+        drop_concentration(characters, index);
+        Some(format!("{} {} against DC {}, and lost concentration on {}", label, roll_string, dc, spell))
+    }
+}
+
+///
+/// Prints a character's concentration and conditions, numbering conditions for removal
+///
+fn print_conditions(character: &Character) {
+    let title = format!("{}/{}", character.character_type, character.name);
+    let concentration = match &character.concentration {
+        Some(spell) => format!("Concentrating on {}", spell),
+        None => "Not concentrating".to_string(),
+    };
+    let conditions: Vec<String> = character.conditions.iter().enumerate().map(|(number, condition)| match (&condition.spell, &condition.caster) {
+        (Some(spell), Some(caster)) => format!("{}. {} ({}, {})", number+1, condition.name, spell, caster),
+        _ => format!("{}. {}", number+1, condition.name),
+    }).collect();
+    let width = render::fit_width(&[vec![title.clone(), concentration.clone()], conditions.clone()].concat());
+
+    render::top(width);
+    render::centered(&title.bold().to_string(), width);
+    render::divider(width);
+    render::centered(&concentration, width);
+    for condition in &conditions {
+        render::dotted(width);
+        render::centered(condition, width);
+    }
+    render::bottom(width);
+    println!();
+}
+
+///
+/// Starts concentration on a spell, then links the conditions it applies to each affected creature
+///
+fn concentrate(characters: &mut [Character], index: usize) {
+    println!("Enter the spell being concentrated on:");
+    let spell = titlecase(&user_input::input());
+
+    // Casting a new concentration spell ends the previous one
+    drop_concentration(characters, index);
+    characters[index].concentration = Some(spell.clone());
+    let caster = caster_label(&characters[index]);
+    let caster_id = characters[index].id;
+    log::record_for(EventKind::Condition, &format!("{} started concentrating on {}", caster, spell), None, &[caster_id]);
+
+    loop {
+        encounter::print_creatures(characters);
+        println!("Choose or enter the number, name or #ID of a creature affected by {}, or type \"0\" when done:", spell);
+        let target = match encounter::creature_input(characters) {
+            Some(index) => index + 1,
+            None => break,
+        };
+
+        println!("\nEnter the condition {} applies to {}:", spell, characters[target-1].name);
+        let name = titlecase(&user_input::input());
+        log::record_for(EventKind::Condition, &format!("{}/{} is {} from {}'s {}", characters[target-1].character_type, characters[target-1].name, name, caster, spell), None, &[characters[target-1].id, caster_id]);
+        characters[target-1].conditions.push(Condition {
+            name,
+            spell: Some(spell.clone()),
+            caster: Some(caster.clone()),
+            caster_id: Some(caster_id),
+        });
+    }
+}
+
+///
+/// Menu used to add and remove conditions and to manage a caster's concentration
+///
+pub fn manage_conditions() {
+    let mut characters = encounter::load_encounter_file();
+
+    loop {
+        encounter::print_creatures(&characters);
+        println!("Choose or enter the number, name or #ID of a creature to manage conditions for, or type \"0\" to return: ");
+        let number = match encounter::creature_input(&characters) {
+            Some(index) => index + 1,
+            None => break,
+        };
+
+        println!();
+        print_conditions(&characters[number-1]);
+        println!("(a)dd condition, (r)emove condition, (c)oncentrate on a spell, or (d)rop concentration:");
+        let input = user_input::input();
+        println!();
+        match input.as_str() {
+            "a" => {
+                println!("Enter condition name:");
+                let name = titlecase(&user_input::input());
+                log::record_for(EventKind::Condition, &format!("{} is now {}", caster_label(&characters[number-1]), name), None, &[characters[number-1].id]);
+                characters[number-1].conditions.push(Condition {
+                    name,
+                    spell: None,
+                    caster: None,
+                    caster_id: None,
+                });
+            },
+            "r" => {
+                println!("Enter the number of the condition to remove:");
+                let condition: usize = user_input::usize_input();
+                if condition != 0 && condition <= characters[number-1].conditions.len() {
+                    let removed = characters[number-1].conditions.remove(condition-1);
+                    log::record_for(EventKind::Condition, &format!("{} is no longer {}", caster_label(&characters[number-1]), removed.name), None, &[characters[number-1].id]);
+                }
+            },
+            "c" => {concentrate(&mut characters, number-1);},
+            "d" => {
+                if let Some(spell) = &characters[number-1].concentration {
+                    log::record_for(EventKind::Condition, &format!("{} stopped concentrating on {}", caster_label(&characters[number-1]), spell), None, &[characters[number-1].id]);
+                }
+                drop_concentration(&mut characters, number-1);
+            },
+            _ => {
+                println!("Invalid command!");
+                continue;
+            }
+        }
+        // Saves new file contents
+        encounter::save_encounter_file(&mut characters);
+    }
+}
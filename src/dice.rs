@@ -0,0 +1,178 @@
+use rand::Rng;
+
+///
+/// Most dice a single expression can roll, which keeps a typo like "999999d6" from hanging the tracker
+///
+pub const MAX_DICE: i32 = 100;
+
+///
+/// Most sides a die can have
+///
+pub const MAX_SIDES: i32 = 1000;
+
+///
+/// A single term of a dice expression, either some dice to roll or a flat modifier
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Term {
+    Dice { count: i32, sides: i32 },
+    Flat(i32),
+}
+
+///
+/// Parses a dice expression such as "2d6+3", "d20" or "1d8+1d6-1" into signed terms without rolling anything.
+/// Returns None if it isn't a valid expression or rolls more than [MAX_DICE] dice or dice with more than [MAX_SIDES] sides
+///
+pub fn parse(expression: &str) -> Option<Vec<(i32, Term)>> {
+    let expression: String = expression.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+    if expression.is_empty() {
+        return None;
+    }
+
+    // Splits the expression into signed terms, keeping each term's sign
+    let mut parts: Vec<(i32, String)> = Vec::new();
+    let mut sign = 1;
+    let mut current = String::new();
+    for c in expression.chars() {
+        if c == '+' || c == '-' {
+            if !current.is_empty() {
+                parts.push((sign, current.clone()));
+                current.clear();
+            }
+            sign = if c == '-' {-1} else {1};
+        } else {
+            current.push(c);
+        }
+    }
+    if current.is_empty() {
+        return None;
+    }
+    parts.push((sign, current));
+
+    let mut terms = Vec::new();
+    let mut dice = 0;
+    for (sign, part) in parts {
+        let term = match part.split_once('d') {
+            Some((count, sides)) => {
+                let count: i32 = if count.is_empty() {1} else {count.parse().ok()?};
+                let sides: i32 = sides.parse().ok()?;
+                if !(1..=MAX_DICE).contains(&count) || !(1..=MAX_SIDES).contains(&sides) {
+                    return None;
+                }
+                dice += count;
+                if dice > MAX_DICE {
+                    return None;
+                }
+                Term::Dice { count, sides }
+            },
+            None => Term::Flat(part.parse().ok()?),
+        };
+        terms.push((sign, term));
+    }
+    Some(terms)
+}
+
+///
+/// Rolls a dice expression such as "2d6+3", "d20" or "1d8+1d6-1", returning the total and a breakdown of each roll.
+/// Returns None if the expression can't be parsed or its total doesn't fit
+///
+pub fn roll(expression: &str) -> Option<(i32, String)> {
+    let mut total: i32 = 0;
+    let mut breakdown: Vec<String> = Vec::new();
+    for (sign, term) in parse(expression)? {
+        let (value, description) = match term {
+            Term::Dice { count, sides } => {
+                let rolls: Vec<i32> = (0..count).map(|_| rand::thread_rng().gen_range(1..=sides)).collect();
+                let rolled: Vec<String> = rolls.iter().map(|r| r.to_string()).collect();
+                (rolls.iter().sum::<i32>(), format!("{}d{} ({})", count, sides, rolled.join(", ")))
+            },
+            Term::Flat(value) => (value, value.to_string()),
+        };
+        total = total.checked_add(value.checked_mul(sign)?)?;
+        if breakdown.is_empty() {
+            breakdown.push(if sign < 0 {format!("-{}", description)} else {description});
+        } else {
+            breakdown.push(format!("{} {}", if sign < 0 {"-"} else {"+"}, description));
+        }
+    }
+    Some((total, format!("{} = {}", breakdown.join(" "), total)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_signed_terms() {
+        let terms = parse("2d6 + 3 - d4").unwrap();
+        assert_eq!(terms, vec![(1, Term::Dice { count: 2, sides: 6 }), (1, Term::Flat(3)), (-1, Term::Dice { count: 1, sides: 4 })]);
+    }
+
+    #[test]
+    fn parse_reads_a_leading_minus_and_capital_d() {
+        assert_eq!(parse("-1D8").unwrap(), vec![(-1, Term::Dice { count: 1, sides: 8 })]);
+    }
+
+    #[test]
+    fn parse_rejects_bad_input() {
+        for expression in ["", "   ", "abc", "2d", "d", "2d6+", "2x6", "1.5d6", "2d6d4", "+"] {
+            assert_eq!(parse(expression), None, "{:?} should not parse", expression);
+        }
+    }
+
+    #[test]
+    fn parse_rejects_zero_dice_and_sides() {
+        assert_eq!(parse("0d6"), None);
+        assert_eq!(parse("2d0"), None);
+    }
+
+    #[test]
+    fn parse_caps_dice_and_sides() {
+        assert!(parse(&format!("{}d{}", MAX_DICE, MAX_SIDES)).is_some());
+        assert_eq!(parse(&format!("{}d6", MAX_DICE + 1)), None);
+        assert_eq!(parse(&format!("1d{}", MAX_SIDES + 1)), None);
+        assert_eq!(parse("999999999999d6"), None);
+    }
+
+    #[test]
+    fn parse_caps_dice_across_terms() {
+        assert!(parse(&format!("{}d6+{}d6", MAX_DICE / 2, MAX_DICE / 2)).is_some());
+        assert_eq!(parse(&format!("{}d6+{}d6", MAX_DICE, 1)), None);
+    }
+
+    #[test]
+    fn roll_adds_flat_terms() {
+        assert_eq!(roll("5").unwrap().0, 5);
+        assert_eq!(roll("10-3+1").unwrap(), (8, "10 - 3 + 1 = 8".to_string()));
+    }
+
+    #[test]
+    fn roll_stays_in_bounds() {
+        for _ in 0..200 {
+            let (total, _) = roll("2d6+3").unwrap();
+            assert!((5..=15).contains(&total), "2d6+3 rolled {}", total);
+            let (total, _) = roll("-1d4").unwrap();
+            assert!((-4..=-1).contains(&total), "-1d4 rolled {}", total);
+        }
+    }
+
+    #[test]
+    fn roll_describes_each_die() {
+        let (total, breakdown) = roll("3d1+2").unwrap();
+        assert_eq!(total, 5);
+        assert_eq!(breakdown, "3d1 (1, 1, 1) + 2 = 5");
+    }
+
+    #[test]
+    fn roll_rejects_overflowing_totals() {
+        assert_eq!(roll("2147483647+1"), None);
+        assert_eq!(roll("-2147483647-2"), None);
+        assert!(roll("2147483647").is_some());
+    }
+
+    #[test]
+    fn roll_rejects_bad_input() {
+        assert_eq!(roll("fireball"), None);
+        assert_eq!(roll(&format!("{}d6", MAX_DICE + 1)), None);
+    }
+}
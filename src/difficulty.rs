@@ -0,0 +1,93 @@
+use crate::encounter::Character;
+use crate::stat_search;
+
+///
+/// XP thresholds per character level from the DMG, in Easy/Medium/Hard/Deadly order
+///
+const THRESHOLDS: [[i32; 4]; 20] = [
+    [25, 50, 75, 100],
+    [50, 100, 150, 200],
+    [75, 150, 225, 400],
+    [125, 250, 375, 500],
+    [250, 500, 750, 1100],
+    [300, 600, 900, 1400],
+    [350, 750, 1100, 1700],
+    [450, 900, 1400, 2100],
+    [550, 1100, 1600, 2400],
+    [600, 1200, 1900, 2800],
+    [800, 1600, 2400, 3600],
+    [1000, 2000, 3000, 4500],
+    [1100, 2200, 3400, 5100],
+    [1250, 2500, 3800, 5700],
+    [1400, 2800, 4300, 6400],
+    [1600, 3200, 4800, 7200],
+    [2000, 3900, 5900, 8800],
+    [2100, 4200, 6300, 9500],
+    [2400, 4900, 7300, 10900],
+    [2800, 5700, 8500, 12700],
+];
+
+///
+/// Encounter multipliers from the DMG, including the extra steps used for very small and very large parties
+///
+const MULTIPLIERS: [f64; 8] = [0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 4.0, 5.0];
+
+///
+/// Gets the multiplier for a number of monsters, shifted up for parties under 3 and down for parties of 6 or more
+///
+fn multiplier(monsters: usize, players: usize) -> f64 {
+    let mut step: usize = match monsters {
+        0 | 1 => 1,
+        2 => 2,
+        3..=6 => 3,
+        7..=10 => 4,
+        11..=14 => 5,
+        _ => 6,
+    };
+    if players < 3 {
+        step += 1;
+    } else if players >= 6 {
+        step -= 1;
+    }
+    MULTIPLIERS[step]
+}
+
+///
+/// Builds a difficulty report for the encounter from the party's levels and the monsters' XP
+///
+pub fn report(characters: &[Character]) -> Vec<String> {
+    let players: Vec<&Character> = characters.iter().filter(|c| c.character_type == "Player").collect();
+    let monsters: Vec<&Character> = characters.iter().filter(|c| c.character_type != "Player").collect();
+    if players.is_empty() {
+        return vec!["Add some players to calculate the encounter's difficulty".to_string()];
+    }
+
+    // Sums each player's thresholds, counting players without a level as level 1
+    let mut thresholds = [0; 4];
+    for player in &players {
+        let level = player.level.clamp(1, 20) as usize;
+        for (total, threshold) in thresholds.iter_mut().zip(THRESHOLDS[level-1]) {
+            *total += threshold;
+        }
+    }
+
+    let base_xp: i32 = monsters.iter().map(|m| stat_search::monster_xp(&m.character_type).unwrap_or(0)).sum();
+    let multiplier = multiplier(monsters.len(), players.len());
+    let adjusted_xp = (base_xp as f64 * multiplier) as i32;
+
+    let difficulty = match thresholds.iter().rposition(|threshold| adjusted_xp >= *threshold) {
+        Some(0) => "Easy",
+        Some(1) => "Medium",
+        Some(2) => "Hard",
+        Some(_) => "Deadly",
+        None => "Trivial",
+    };
+
+    let levels: Vec<String> = players.iter().map(|p| p.level.to_string()).collect();
+    vec![
+        format!("Party: {} players (levels {})", players.len(), levels.join(", ")),
+        format!("Thresholds: Easy {} | Medium {} | Hard {} | Deadly {}", thresholds[0], thresholds[1], thresholds[2], thresholds[3]),
+        format!("Monsters: {} worth {} XP, x{} multiplier", monsters.len(), base_xp, multiplier),
+        format!("Adjusted XP: {} ({})", adjusted_xp, difficulty),
+    ]
+}
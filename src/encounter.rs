@@ -1,7 +1,17 @@
 use crate::user_input;
+use crate::user_input::Picked;
 use serde::{Serialize, Deserialize};
 use std::fs;
 use crate::stat_search;
+use crate::conditions;
+use crate::difficulty;
+use crate::history;
+use crate::log;
+use crate::log::EventKind;
+use crate::render;
+use crate::turn;
+use crate::party;
+use crate::initiative;
 use titlecase::titlecase;
 use colored::*;
 use rand::Rng;
@@ -11,35 +21,141 @@ extern crate shellexpand;
 ///
 /// Character struct used for printing basic stats on the main menu
 ///
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Character {
+    #[serde(default)]
+    pub id: u64,
     pub name: String,
     pub character_type: String,
     pub ac: i32,
     pub hp: i32,
+    #[serde(default)]
+    pub max_hp: i32,
     pub initiative: i32,
+    #[serde(default)]
+    pub level: i32,
+    #[serde(default)]
+    pub concentration: Option<String>,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    #[serde(default)]
+    pub surprised: bool,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub joins_round: Option<usize>,
 }
 
+///
+/// Condition struct used for tracking effects on a character, optionally linked to a caster's concentration spell
+///
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Condition {
+    pub name: String,
+    #[serde(default)]
+    pub spell: Option<String>,
+    #[serde(default)]
+    pub caster: Option<String>,
+    #[serde(default)]
+    pub caster_id: Option<u64>,
+}
+
+///
+/// HP given to players who aren't in the party roster, since their HP isn't tracked
+///
+pub const PLAYER_HP: i32 = 999999;
+
+///
+/// Most copies of a monster that can be added in one go
+///
+pub const MAX_COUNT: i32 = 100;
+
 ///
 /// Loads encounter file if it exists
 ///
-fn load_encounter_file() -> Vec<Character> {
+pub fn load_encounter_file() -> Vec<Character> {
     let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker/encounter.json").into_owned();
     let path = PathBuf::from(expanded_path);
     let content = fs::read_to_string(&path).unwrap_or_else(|_| "[]".to_string());
-    serde_json::from_str(&content).unwrap_or_else(|_| {
+    let mut characters = serde_json::from_str(&content).unwrap_or_else(|_| {
         println!("Error parsing JSON. Starting with an empty list.");
         Vec::new()
-    })
+    });
+    // Encounters saved before creatures had IDs get them in file order, which is the same on every load
+    assign_ids(&mut characters);
+    characters
+}
+
+///
+/// Gives each creature without an ID the next one after the highest in the list
+///
+fn assign_ids(characters: &mut [Character]) {
+    let mut next = characters.iter().map(|character| character.id).max().unwrap_or(0);
+    for character in characters.iter_mut().filter(|character| character.id == 0) {
+        next += 1;
+        character.id = next;
+    }
+}
+
+///
+/// Hands out IDs to creatures that don't have one yet, returning them in list order. The last one is kept in
+/// ~/.config/dnd-encounter-tracker/last_id.json so IDs are never reused, even after the creature that had one is removed
+///
+fn allocate_ids(characters: &mut [Character], saved: &[Character]) -> Vec<u64> {
+    if characters.iter().all(|character| character.id != 0) {
+        return Vec::new();
+    }
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker/last_id.json").into_owned();
+    let path = PathBuf::from(expanded_path);
+    let last: u64 = fs::read_to_string(&path).ok().and_then(|content| content.trim().parse().ok()).unwrap_or(0);
+    let mut next = characters.iter().chain(saved).map(|character| character.id).fold(last, std::cmp::max);
+
+    let mut ids = Vec::new();
+    for character in characters.iter_mut().filter(|character| character.id == 0) {
+        next += 1;
+        character.id = next;
+        ids.push(next);
+    }
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker").into_owned();
+    std::fs::create_dir_all(PathBuf::from(expanded_path)).expect("Failed to create directory");
+    std::fs::write(&path, next.to_string()).expect("Unable to write to file");
+    ids
 }
 
 ///
-/// Saves encounter file to ~/.config/dnd-encounter-tracker/encounter.json and creates directory if it doesn't exist
+/// Saves encounter file, recording the previous encounter in the undo history. New creatures get their IDs here,
+/// which are returned in the order the creatures were added. The turn stays with the same creature however the
+/// order changed
 ///
-fn save_encounter_file(characters: &mut Vec<Character>) {
+pub fn save_encounter_file(characters: &mut [Character]) -> Vec<u64> {
+    let saved = load_encounter_file();
+    let ids = allocate_ids(characters, &saved);
     // Sorts characters by initiative (doesn't take dex into account)
     characters.sort_by_key(|char| -char.initiative);
+    let state = turn::load_turn();
+    history::record(characters, &state);
+    let state = turn::follow(&state, &saved, characters);
+    write_encounter_file(characters);
+    turn::write_turn(&state);
+    ids
+}
+
+///
+/// Saves the encounter and logs each added creature against the ID it was given. The messages are in the same order
+/// the creatures were added to the list
+///
+pub fn save_added(characters: &mut [Character], added: &[String]) -> Vec<u64> {
+    let ids = save_encounter_file(characters);
+    for (message, id) in added.iter().zip(&ids) {
+        log::record_for(EventKind::Add, message, None, &[*id]);
+    }
+    ids
+}
 
+///
+/// Writes encounter file to ~/.config/dnd-encounter-tracker/encounter.json and creates directory if it doesn't exist
+///
+pub fn write_encounter_file(characters: &[Character]) {
     // Saves changes to encounter file, and creates the folder structure
     let json_characters = serde_json::to_string_pretty(&characters).unwrap();
     let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker").into_owned();
@@ -50,7 +166,65 @@ fn save_encounter_file(characters: &mut Vec<Character>) {
     std::fs::write(&path, json_characters).expect("Unable to write to file");
 }
 
-fn input_break_check(input: &str) -> usize {
+///
+/// Applies damage to a character, rolling a concentration save if they're concentrating on a spell.
+/// A player's concentration roll can be given up front, otherwise they're asked for it
+///
+pub fn apply_damage(characters: &mut [Character], index: usize, damage: i32, player_roll: Option<i32>) -> Option<String> {
+    characters[index].hp -= damage;
+    let label = format!("{}/{}", characters[index].character_type, characters[index].name);
+    if damage >= 0 {
+        log::record_for(EventKind::Damage, &format!("{} took {} damage, leaving {} HP", label, damage, characters[index].hp), Some(damage), &[characters[index].id]);
+    } else {
+        log::record_for(EventKind::Heal, &format!("{} healed {} HP, up to {} HP", label, -damage, characters[index].hp), Some(-damage), &[characters[index].id]);
+    }
+
+    if damage > 0 && characters[index].concentration.is_some() {
+        let result = conditions::concentration_check(characters, index, damage, player_roll);
+        if let Some(result) = &result {
+            log::record_for(EventKind::Save, result, None, &[characters[index].id]);
+        }
+        result
+    } else {
+        None
+    }
+}
+
+///
+/// Rolls a saving throw for a character, using their statblock for monsters and asking for the roll from players
+/// unless it was given up front
+///
+pub fn roll_save(character: &Character, ability: &str, dc: i32, player_roll: Option<i32>) -> (String, i32) {
+    match (stat_search::save_bonus(&character.character_type, ability), player_roll) {
+        (Some(modifier), _) => {
+            let roll = rand::thread_rng().gen_range(1..21);
+            (format!("rolled a {}{:+} = {}", roll, modifier, roll + modifier), roll + modifier)
+        },
+        (None, Some(total)) => (format!("rolled a {}", total), total),
+        (None, None) => {
+            println!("\nEnter {}/{}'s {} saving throw (DC {}):", character.character_type, character.name, ability.to_uppercase(), dc);
+            let total = user_input::int_input();
+            (format!("rolled a {}", total), total)
+        }
+    }
+}
+
+///
+/// Picks a name that isn't already used in the encounter, numbering repeats (e.g. "Orc 2")
+///
+pub fn unique_name(base: &str, characters: &[Character], numbered: bool) -> String {
+    let taken = |name: &str| characters.iter().any(|c| c.name.to_lowercase() == name.to_lowercase());
+    if !numbered && !taken(base) {
+        return base.to_string();
+    }
+    let mut number = 1;
+    while taken(&format!("{} {}", base, number)) {
+        number += 1;
+    }
+    format!("{} {}", base, number)
+}
+
+pub fn input_break_check(input: &str) -> usize {
     match input {
         "done" => 0,
         "0" => 0,
@@ -58,11 +232,215 @@ fn input_break_check(input: &str) -> usize {
     }
 }
 
+///
+/// Gets a creature's maximum HP, falling back to its statblock for encounters saved before max HP was tracked
+///
+pub fn max_hp(character: &Character) -> i32 {
+    if character.max_hp > 0 {
+        return character.max_hp;
+    }
+    match stat_search::find_statblock(&character.character_type) {
+        Some(creature) => std::cmp::max(creature.health, character.hp),
+        None => character.hp,
+    }
+}
+
+///
+/// Checks whether a player's HP is tracked, which it is for players added from the party roster
+///
+pub fn tracks_player_hp(max_hp: i32) -> bool {
+    max_hp > 0 && max_hp != PLAYER_HP
+}
+
+///
+/// Describes how hurt a creature is without giving away its exact HP
+///
+pub fn health_band(character: &Character) -> &'static str {
+    let max = max_hp(character);
+    if character.hp <= 0 {
+        "Down"
+    } else if character.hp >= max {
+        "Unhurt"
+    } else if character.hp * 2 > max {
+        "Injured"
+    } else if character.hp * 4 > max {
+        "Bloodied"
+    } else {
+        "Critical"
+    }
+}
+
+///
+/// Checks whether players can see a creature, which they can't while it's hidden or hasn't joined the fight yet
+///
+pub fn shown_to_players(character: &Character, round: usize) -> bool {
+    !character.hidden && !turn::waiting(character, round)
+}
+
+///
+/// Describes anything keeping a creature out of the normal turn order or out of the players' sight, for the DM's views
+///
+pub fn status_tags(character: &Character, round: usize) -> Vec<String> {
+    let mut tags = Vec::new();
+    if character.surprised {
+        tags.push("Surprised".to_string());
+    }
+    if character.hidden {
+        tags.push("Hidden".to_string());
+    }
+    if let Some(joins) = character.joins_round.filter(|_| turn::waiting(character, round)) {
+        tags.push(format!("Joins in round {}", joins));
+    }
+    tags
+}
+
+///
+/// Reveals a hidden creature to the players, returning a note if it was hidden
+///
+pub fn reveal(characters: &mut [Character], index: usize) -> Option<String> {
+    if !characters[index].hidden {
+        return None;
+    }
+    characters[index].hidden = false;
+    let message = format!("{}/{} was revealed", characters[index].character_type, characters[index].name);
+    log::record_for(EventKind::Turn, &message, None, &[characters[index].id]);
+    Some(message)
+}
+
+///
+/// Finds the creatures a token refers to: a list number, an ID like "#4", a name, a unique name prefix, or a type/name
+/// pair like "orc/grug"
+///
+pub fn find_creatures(token: &str, characters: &[Character]) -> Vec<usize> {
+    let token = token.trim().to_lowercase();
+    if let Ok(number) = token.parse::<usize>() {
+        return if number >= 1 && number <= characters.len() {vec![number-1]} else {Vec::new()};
+    }
+    // IDs stay with a creature however the order changes, which tells apart creatures with the same name
+    if let Some(id) = token.strip_prefix('#').and_then(|id| id.parse::<u64>().ok()) {
+        return (0..characters.len()).filter(|&i| characters[i].id == id).collect();
+    }
+
+    // Type/name pairs match on both halves, each of which can be a prefix
+    if let Some((character_type, name)) = token.split_once('/') {
+        let character_type = if character_type == "pc" {"player"} else {character_type};
+        return (0..characters.len()).filter(|&i| {
+            characters[i].character_type.to_lowercase().starts_with(character_type.trim()) && characters[i].name.to_lowercase().starts_with(name.trim())
+        }).collect();
+    }
+
+    // Exact names win over prefixes, and names win over types
+    let exact: Vec<usize> = (0..characters.len()).filter(|&i| characters[i].name.to_lowercase() == token).collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+    let prefix: Vec<usize> = (0..characters.len()).filter(|&i| characters[i].name.to_lowercase().starts_with(&token)).collect();
+    if !prefix.is_empty() {
+        return prefix;
+    }
+    (0..characters.len()).filter(|&i| characters[i].character_type.to_lowercase().starts_with(&token)).collect()
+}
+
+///
+/// Asks which of several matching creatures was meant, returning None if the user backs out
+///
+fn choose_creature(token: &str, matches: &[usize], characters: &[Character]) -> Option<usize> {
+    println!("\n\"{}\" matches more than one creature:", token);
+    for (number, &index) in matches.iter().enumerate() {
+        println!("{}. {}/{} (#{}, {} HP)", number+1, characters[index].character_type, characters[index].name, characters[index].id, characters[index].hp);
+    }
+    println!("Enter the number of the one you meant, or type \"0\" to return:");
+    let choice = user_input::usize_input();
+    if choice == 0 || choice > matches.len() {
+        None
+    } else {
+        Some(matches[choice-1])
+    }
+}
+
+///
+/// Describes a creature for picking it from a list
+///
+fn creature_option(character: &Character) -> String {
+    if character.character_type == "Player" && !tracks_player_hp(character.max_hp) {
+        format!("PC/{} (#{})", character.name, character.id)
+    } else {
+        format!("{}/{} (#{}, {} HP)", character.character_type, character.name, character.id, character.hp)
+    }
+}
+
+///
+/// Reads a creature, picked with the arrow keys in a terminal or typed as a number, name or #ID, asking again if
+/// nothing matches. Returns None if the user backs out or types "0" or "done"
+///
+pub fn creature_input(characters: &[Character]) -> Option<usize> {
+    let mut typed = None;
+    if user_input::can_pick(characters.len()) {
+        match user_input::pick(&characters.iter().map(creature_option).collect::<Vec<String>>()) {
+            Picked::Chosen(chosen) => return chosen.first().copied(),
+            Picked::Typed(input) => typed = Some(input),
+            Picked::Cancelled => return None,
+        }
+    }
+    loop {
+        let input = typed.take().unwrap_or_else(user_input::input);
+        if input_break_check(&input) == 0 || input.is_empty() {
+            return None;
+        }
+        let matches = find_creatures(&input, characters);
+        match matches.len() {
+            0 => println!("\nNo creature matches \"{}\". Enter a number, name or #ID, or type \"0\" to return:", input),
+            1 => return Some(matches[0]),
+            _ => return choose_creature(&input, &matches, characters),
+        }
+    }
+}
+
+///
+/// Reads several creatures, picked with the arrow keys in a terminal or typed as numbers, names or #IDs separated by
+/// commas (numbers can also be separated by spaces)
+///
+pub fn creature_list_input(characters: &[Character]) -> Vec<usize> {
+    let input = if user_input::can_pick(characters.len()) {
+        match user_input::pick_many(&characters.iter().map(creature_option).collect::<Vec<String>>()) {
+            Picked::Chosen(chosen) => return chosen,
+            Picked::Typed(input) => input,
+            Picked::Cancelled => return Vec::new(),
+        }
+    } else {
+        user_input::input()
+    };
+    let mut tokens = Vec::new();
+    for part in input.split(',').map(|part| part.trim()).filter(|part| !part.is_empty()) {
+        if part.split_whitespace().all(|word| word.parse::<usize>().is_ok()) {
+            tokens.extend(part.split_whitespace().map(|word| word.to_string()));
+        } else {
+            tokens.push(part.to_string());
+        }
+    }
+
+    let mut selected = Vec::new();
+    for token in tokens {
+        let matches = find_creatures(&token, characters);
+        let index = match matches.len() {
+            0 => {println!("No creature matches \"{}\", skipping it", token); None},
+            1 => Some(matches[0]),
+            _ => choose_creature(&token, &matches, characters),
+        };
+        if let Some(index) = index {
+            if !selected.contains(&index) {
+                selected.push(index);
+            }
+        }
+    }
+    selected
+}
+
 ///
 /// Grabs stats from [stat_search] and rolls for a hit and damage against a character's AC
 ///
 pub fn attack() {
-    let characters = load_encounter_file();
+    let mut characters = load_encounter_file();
     
     // Initializes damage output strings to null
     let mut attack_string_1 = "Null".to_string();
@@ -79,17 +457,17 @@ pub fn attack() {
             } else {println!();}
         }
 
-        println!("Enter the number of the attacking creature (can't be a player), or type \"0\" to return:");
-        let attacker: usize = user_input::usize_input();
-        if input_break_check(attacker.to_string().as_str()) == 0 || attacker > characters.len() || &characters[attacker-1].character_type == "Player" {
-            break;
-        }
+        println!("Choose or enter the number, name or #ID of the attacking creature (can't be a player), or type \"0\" to return:");
+        let attacker = match creature_input(&characters) {
+            Some(index) if characters[index].character_type != "Player" => index + 1,
+            _ => break,
+        };
 
-        println!("\nEnter the number of the attacked creature, or type \"0\" to return:");
-        let attacked: usize = user_input::usize_input();
-        if input_break_check(attacked.to_string().as_str()) == 0 || attacked > characters.len() {
-            break;
-        }
+        println!("\nChoose or enter the number, name or #ID of the attacked creature, or type \"0\" to return:");
+        let attacked = match creature_input(&characters) {
+            Some(index) => index + 1,
+            None => break,
+        };
 
         // Displays attacks based on the character's type in the encounter file
         println!();
@@ -108,29 +486,85 @@ pub fn attack() {
             break;
         }
 
-        // Rolls for attack and damage, comparing it to target's AC
-        let attack_roll = rand::thread_rng().gen_range(1..21) + attack_var.attack_modifier;
-        if attack_roll - attack_var.attack_modifier == 20 {
-            attack_string_1 = format!("Rolled a nat 20 and dealt a critical hit!");
-            let mut damage_roll = rand::thread_rng().gen_range(1..attack_var.damage_dice[1]+1);
-            for _ in 0..attack_var.damage_dice[0] {
-                damage_roll += rand::thread_rng().gen_range(1..attack_var.damage_dice[1]+1);
+        // Rolls the attack and applies its damage, which may ask players for their saving throws
+        let (lines, _, changed) = resolve_attack(&mut characters, attacker-1, attacked-1, &attack_var, None, None);
+        attack_string_1 = lines[0].clone();
+        attack_string_2 = if lines.len() > 1 {lines[1..].join("\n")} else {"Null".to_string()};
+        if changed {
+            save_encounter_file(&mut characters);
+        }
+    }
+}
+
+///
+/// Rolls an action against a target, records it in the combat log and applies its damage. Players are asked
+/// for any saving throw they have to make unless the roll is given up front. Returns the result lines, the damage dealt
+/// and whether any creature changed, since even a miss reveals a hidden attacker
+///
+pub fn resolve_attack(characters: &mut [Character], attacker: usize, attacked: usize, attack_var: &stat_search::Action, save_roll: Option<i32>, concentration_roll: Option<i32>) -> (Vec<String>, i32, bool) {
+    let attack_string_1;
+    let mut attack_string_2 = None;
+    let mut damage_dealt = 0;
+    let attack_roll = rand::thread_rng().gen_range(1..21) + attack_var.attack_modifier;
+    if let Some(save) = &attack_var.save {
+        // Save-based actions have the target roll against the action's DC instead of rolling to hit
+        let (roll_string, total) = roll_save(&characters[attacked], &save.ability, save.dc, save_roll);
+        let mut damage_roll = rand::thread_rng().gen_range(1..attack_var.damage_dice[1]+1);
+        for _ in 0..attack_var.damage_dice[0]-1 {
+            damage_roll += rand::thread_rng().gen_range(1..attack_var.damage_dice[1]+1);
+        }
+        if total >= save.dc {
+            attack_string_1 = format!("{}/{} {} against a DC {} {} save, and succeeded", characters[attacked].character_type, characters[attacked].name, roll_string, save.dc, save.ability.to_uppercase());
+            if save.half_on_success {
+                damage_dealt = (damage_roll+attack_var.damage_bonus) / 2;
+                attack_string_2 = Some(format!("This dealt half of {}+{} = {} {} damage", damage_roll, attack_var.damage_bonus, damage_dealt, attack_var.damage_type));
             }
-            attack_string_2 = format!("This dealt {}+{} = {} {} damage", damage_roll, attack_var.damage_bonus, damage_roll+attack_var.damage_bonus, attack_var.damage_type);
         } else {
-            if attack_roll >= characters[attacked-1].ac {
-                attack_string_1 = format!("Rolled a {}+{} = {} against {}/{}'s AC of {}, and hit", attack_roll-attack_var.attack_modifier, attack_var.attack_modifier, attack_roll, characters[attacked-1].character_type, characters[attacked-1].name, characters[attacked-1].ac);
-                let mut damage_roll = rand::thread_rng().gen_range(1..attack_var.damage_dice[1]+1);
-                for _ in 0..attack_var.damage_dice[0]-1 {
-                    damage_roll += rand::thread_rng().gen_range(1..attack_var.damage_dice[1]+1);
-                }
-                attack_string_2 = format!("This dealt {}+{} = {} {} damage", damage_roll, attack_var.damage_bonus, damage_roll+attack_var.damage_bonus, attack_var.damage_type);
-            } else {
-                attack_string_1 = format!("Rolled a {}+{} = {} against {}/{}'s AC of {}, and missed", attack_roll-attack_var.attack_modifier, attack_var.attack_modifier, attack_roll, characters[attacked-1].character_type, characters[attacked-1].name, characters[attacked-1].ac);
-                attack_string_2 = "Null".to_string();
-            }
+            attack_string_1 = format!("{}/{} {} against a DC {} {} save, and failed", characters[attacked].character_type, characters[attacked].name, roll_string, save.dc, save.ability.to_uppercase());
+            attack_string_2 = Some(format!("This dealt {}+{} = {} {} damage", damage_roll, attack_var.damage_bonus, damage_roll+attack_var.damage_bonus, attack_var.damage_type));
+            damage_dealt = damage_roll+attack_var.damage_bonus;
         }
+    } else if attack_roll - attack_var.attack_modifier == 20 {
+        attack_string_1 = "Rolled a nat 20 and dealt a critical hit!".to_string();
+        let mut damage_roll = rand::thread_rng().gen_range(1..attack_var.damage_dice[1]+1);
+        for _ in 0..attack_var.damage_dice[0] {
+            damage_roll += rand::thread_rng().gen_range(1..attack_var.damage_dice[1]+1);
+        }
+        attack_string_2 = Some(format!("This dealt {}+{} = {} {} damage", damage_roll, attack_var.damage_bonus, damage_roll+attack_var.damage_bonus, attack_var.damage_type));
+        damage_dealt = damage_roll+attack_var.damage_bonus;
+    } else if attack_roll >= characters[attacked].ac {
+        attack_string_1 = format!("Rolled a {}+{} = {} against {}/{}'s AC of {}, and hit", attack_roll-attack_var.attack_modifier, attack_var.attack_modifier, attack_roll, characters[attacked].character_type, characters[attacked].name, characters[attacked].ac);
+        let mut damage_roll = rand::thread_rng().gen_range(1..attack_var.damage_dice[1]+1);
+        for _ in 0..attack_var.damage_dice[0]-1 {
+            damage_roll += rand::thread_rng().gen_range(1..attack_var.damage_dice[1]+1);
+        }
+        attack_string_2 = Some(format!("This dealt {}+{} = {} {} damage", damage_roll, attack_var.damage_bonus, damage_roll+attack_var.damage_bonus, attack_var.damage_type));
+        damage_dealt = damage_roll+attack_var.damage_bonus;
+    } else {
+        attack_string_1 = format!("Rolled a {}+{} = {} against {}/{}'s AC of {}, and missed", attack_roll-attack_var.attack_modifier, attack_var.attack_modifier, attack_roll, characters[attacked].character_type, characters[attacked].name, characters[attacked].ac);
     }
+
+    // Records the attack in the combat log before any damage is applied
+    let attack_kind = if damage_dealt > 0 {EventKind::Hit} else {EventKind::Miss};
+    log::record_for(attack_kind, &format!("{}/{} used {} on {}/{}: {}", characters[attacker].character_type, characters[attacker].name, attack_var.name, characters[attacked].character_type, characters[attacked].name, attack_string_1), None, &[characters[attacker].id, characters[attacked].id]);
+
+    // Applies the damage to the target, which may break their concentration
+    let mut lines = vec![attack_string_1];
+    lines.extend(attack_string_2);
+
+    // Attacking gives away a hidden creature's position, as does being hit
+    let revealed = reveal(characters, attacker);
+    let changed = revealed.is_some() || damage_dealt > 0;
+    lines.extend(revealed);
+    if damage_dealt > 0 {
+        lines.extend(reveal(characters, attacked));
+    }
+    if damage_dealt > 0 {
+        if let Some(result) = apply_damage(characters, attacked, damage_dealt, concentration_roll) {
+            lines.push(result);
+        }
+    }
+    (lines, damage_dealt, changed)
 }
 
 ///
@@ -141,28 +575,38 @@ pub fn edit_creature() {
 
     loop {
         print_creatures(&characters);
-        println!("Enter the number of a creature to edit, or type \"0\" to return: ");
-        let number: usize = user_input::usize_input();
-        if input_break_check(number.to_string().as_str()) == 0 || number > characters.len() {
-            break;
-        }
+        println!("Choose or enter the number, name or #ID of a creature to edit, or type \"0\" to return: ");
+        let number = match creature_input(&characters) {
+            Some(index) => index + 1,
+            None => break,
+        };
 
-        println!("{}", format!("╔{:═<35}╗", "═"));
-        println!("{}", format!("║{:^35}║", format!("Editing {}/{}", characters[number-1].character_type, characters[number-1].name).bold()));
-        println!("{}", format!("╟{:─<35}╢", "─"));
-        println!("{}", format!("║{:^35}║", format!("1. Name: {}", characters[number-1].name)));
-        println!("{}", format!("╟{:┄<35}╢", "┄"));
-        println!("{}", format!("║{:^35}║", format!("2. Race: {}", characters[number-1].character_type)));
-        println!("{}", format!("╟{:┄<35}╢", "┄"));
-        println!("{}", format!("║{:^35}║", format!("3. AC: {}", characters[number-1].ac)));
-        println!("{}", format!("╟{:┄<35}╢", "┄"));
-        println!("{}", format!("║{:^35}║", format!("4. HP: {}", characters[number-1].hp)));
-        println!("{}", format!("╟{:┄<35}╢", "┄"));
-        println!("{}", format!("║{:^35}║", format!("5. Initiative: {}", characters[number-1].initiative)));
-        println!("{}", format!("╚{:═<35}╝", "═"));
+        let character = &characters[number-1];
+        let mut fields = vec![
+            format!("1. Name: {}", character.name),
+            format!("2. Race: {}", character.character_type),
+            format!("3. AC: {}", character.ac),
+            format!("4. HP: {}", character.hp),
+            format!("5. Initiative: {}", character.initiative),
+        ];
+        if character.character_type == "Player" {
+            fields.push(format!("6. Level: {}", character.level));
+        }
+        let title = format!("Editing {}/{}", character.character_type, character.name);
+        let width = render::fit_width(&[vec![title.clone()], fields.clone()].concat());
+        render::top(width);
+        render::centered(&title.bold().to_string(), width);
+        render::divider(width);
+        for (index, field) in fields.iter().enumerate() {
+            if index != 0 {
+                render::dotted(width);
+            }
+            render::centered(field, width);
+        }
+        render::bottom(width);
         println!("\nEnter the number of the field to edit:");
         let input: usize = user_input::usize_input();
-        if input_break_check(input.to_string().as_str()) == 0 || input > 5 {
+        if input_break_check(input.to_string().as_str()) == 0 || input > 6 || (input == 6 && characters[number-1].character_type != "Player") {
             break;
         }
         
@@ -187,6 +631,10 @@ pub fn edit_creature() {
             5 => {
                 println!("Enter new initiative:");
                 characters[number-1].initiative = user_input::int_input();
+            },
+            6 => {
+                println!("Enter new level:");
+                characters[number-1].level = user_input::int_input();
             }
             _ => {
                 println!("Invalid input!");
@@ -201,31 +649,42 @@ pub fn edit_creature() {
 ///
 /// Takes a vector of characters and prints each character in number order, allowing for selection in different functions
 ///
-fn print_creatures(characters: &Vec<Character>) {
+pub fn print_creatures(characters: &[Character]) {
     // Escape code to clear screen
     print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
     
-    // This variable is used to actually number each creature
-    let mut number = 1;
-    if characters.len() != 0 {
-        println!("{}", format!("╔{:═<35}╗", "═"));
-        println!("{}", format!("║{:^35}║", format!("Current Encounter:").bold()));
-        for creature in characters {
-            if number == 1 {
-                println!("{}", format!("╟{:─<35}╢", "─"));
+    if !characters.is_empty() {
+        // Numbers each creature, building the lines first so the box can be sized to fit them
+        let round = turn::load_turn().round;
+        let lines: Vec<String> = characters.iter().enumerate().map(|(index, creature)| {
+            let line = if creature.character_type == "Player" && tracks_player_hp(creature.max_hp) {
+                format!("{}. PC/{} (#{}), {} HP", index + 1, creature.name, creature.id, creature.hp)
+            } else if creature.character_type == "Player" {
+                format!("{}. PC/{} (#{})", index + 1, creature.name, creature.id)
             } else {
-                println!("{}", format!("╟{:┄<35}╢", "┄"));
+                format!("{}. {}/{} (#{}), {} HP", index + 1, creature.character_type, creature.name, creature.id, creature.hp)
+            };
+            let tags = status_tags(creature, round);
+            if tags.is_empty() {line} else {format!("{} ({})", line, tags.join(", "))}
+        }).collect();
+        let width = render::fit_width(&lines);
+
+        render::top(width);
+        render::centered(&"Current Encounter:".bold().to_string(), width);
+        for (index, line) in lines.iter().enumerate() {
+            if index == 0 {
+                render::divider(width);
+            } else {
+                render::dotted(width);
             }
-            if creature.character_type == "Player" {
-                let string = format!("{}. PC/{}", number, creature.name);
-                println!("║{:^35}║", string.blue());
+            if characters[index].character_type == "Player" {
+                render::centered(&line.blue().to_string(), width);
             } else {
-                let string = format!("{}. {}/{}, {} HP", number, creature.character_type, creature.name, creature.hp);
-                println!("║{:^35}║", string.red());
+                render::centered(&line.red().to_string(), width);
             }
-            number +=1;
         }
-        println!("{}\n", format!("╚{:═<35}╝", "═"));
+        render::bottom(width);
+        println!();
     }
 }
 
@@ -236,17 +695,20 @@ pub fn remove_creature() {
     let mut characters = load_encounter_file();
     
     loop {
-        if characters.len() == 0 {
+        if characters.is_empty() {
             break;
         }
         print_creatures(&characters);
-        println!("Enter the number of a creature to remove, or type \"0\" to return: ");
-        let number: usize = user_input::usize_input();
-        if input_break_check(number.to_string().as_str()) == 0 || number > characters.len() {
-            break;
-        }
+        println!("Choose or enter the number, name or #ID of a creature to remove, or type \"0\" to return: ");
+        let number = match creature_input(&characters) {
+            Some(index) => index + 1,
+            None => break,
+        };
         println!();
 
+        // Ends any spell the creature was concentrating on before removing it
+        conditions::drop_concentration(&mut characters, number-1);
+        log::record_for(EventKind::Remove, &format!("Removed {}/{}", characters[number-1].character_type, characters[number-1].name), None, &[characters[number-1].id]);
         characters.remove(number-1);
 
         save_encounter_file(&mut characters);
@@ -258,19 +720,28 @@ pub fn remove_creature() {
 ///
 pub fn damage_creature() {
     let mut characters = load_encounter_file();
+
+    // Stores the result of the last concentration save so it survives the screen clear
+    let mut concentration_string = "Null".to_string();
     
     loop {
         print_creatures(&characters);
-        println!("Enter the number of a creature to damage, or type \"0\" to return: ");
-        let number: usize = user_input::usize_input();
-        if input_break_check(number.to_string().as_str()) == 0 || number > characters.len() {
-            break;
+        if concentration_string != "Null" {
+            println!("{}\n", concentration_string);
+            concentration_string = "Null".to_string();
         }
+        println!("Choose or enter the number, name or #ID of a creature to damage, or type \"0\" to return: ");
+        let number = match creature_input(&characters) {
+            Some(index) => index + 1,
+            None => break,
+        };
 
         println!("\nDamaging {}/{}", characters[number-1].character_type, characters[number-1].name);
         println!("Enter damage dealt (negatives are used for healing):");
         let damage: i32 = user_input::int_input();
-        characters[number-1].hp -= damage;
+        if let Some(result) = apply_damage(&mut characters, number-1, damage, None) {
+            concentration_string = result;
+        }
 
         save_encounter_file(&mut characters);
     }
@@ -297,22 +768,49 @@ fn add_monster() -> Character {
 fn add_player() -> Character {
     println!("Enter player name:");
     let name = titlecase(&user_input::input());
+
+    // Players in the party roster only need their initiative
+    if let Some(member) = party::find_member(&name) {
+        println!("\nEnter {}'s rolled initiative, or press enter to roll d20{:+}:", member.name, member.initiative_bonus);
+        let input = user_input::input();
+        let initiative = input.parse().unwrap_or_else(|_| party::roll_initiative(&member));
+        println!("\nPlayer {} added from the party roster with initiative {}!\n", member.name, initiative);
+        return party::to_character(&member, initiative);
+    }
+
     println!("\nEnter {}'s AC:", name);
     let ac = user_input::int_input();
 
     // Note that hp is not used, but is necessary for the Character struct
-    let hp = 999999;
-    println!("\nEnter {}'s rolled initiative:", name);
-    let initiative = user_input::int_input();
+    let hp = PLAYER_HP;
+    println!("\nEnter {}'s level:", name);
+    let level = user_input::int_input();
+    println!("\nEnter {}'s rolled initiative, or press enter to ask for it when combat starts:", name);
+    let initiative = loop {
+        let input = user_input::input();
+        match input.parse::<i32>() {
+            Ok(initiative) => break initiative,
+            Err(..) if input.is_empty() => break 0,
+            Err(..) => println!("\nPlease enter a valid integer:"),
+        }
+    };
     println!("\nPlayer {} added!\n", name);
 
     // Returns the character
     Character {
+        id: 0,
         name,
         character_type: "Player".to_string(),
         ac,
         hp,
+        max_hp: hp,
         initiative,
+        level,
+        concentration: None,
+        conditions: Vec::new(),
+        surprised: false,
+        hidden: false,
+        joins_round: None,
     }
 }
 
@@ -322,9 +820,21 @@ fn add_player() -> Character {
 pub fn add_character() {
     let mut characters = load_encounter_file();
 
+    // Stores the last difficulty report so it survives the screen clear
+    let mut report: Vec<String> = Vec::new();
+    // Log messages for everyone added, recorded once they're saved with their IDs
+    let mut added: Vec<String> = Vec::new();
+
     loop {
         print_creatures(&characters);
-        println!("Add either a (m)onster or (p)layer, or type \"done\" to return: ");
+        for line in &report {
+            println!("{}", line);
+        }
+        if !report.is_empty() {
+            println!();
+        }
+        report.clear();
+        println!("Add a (m)onster, a (p)layer or the whole (g)roup from the party roster, check the encounter's (d)ifficulty, (s)tart combat, or type \"done\" to return: ");
         let input: String = user_input::input();
         println!();
         if input_break_check(&input) == 0 {
@@ -332,12 +842,105 @@ pub fn add_character() {
         }
 
         match input.as_str() {
-            "m" => {characters.push(add_monster());}
-            "p" => {characters.push(add_player());}
+            "m" | "p" => {
+                let character = if input == "m" {add_monster()} else {add_player()};
+                added.push(format!("Added {}/{} with initiative {}", character.character_type, character.name, character.initiative));
+                characters.push(character);
+            }
+            "g" => {added.extend(party::add_party(&mut characters));}
+            "d" => {report = difficulty::report(&characters);}
+            "s" if !characters.is_empty() => {
+                save_added(&mut characters, &added);
+                initiative::start_combat();
+                return;
+            }
             _ => {
                 println!("Invalid command!");
             }
         }
     }
-    save_encounter_file(&mut characters);
+    save_added(&mut characters, &added);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn creature(id: u64, name: &str, character_type: &str) -> Character {
+        Character {
+            id,
+            name: name.to_string(),
+            character_type: character_type.to_string(),
+            ac: 12,
+            hp: 10,
+            max_hp: 10,
+            initiative: 10,
+            level: 0,
+            concentration: None,
+            conditions: Vec::new(),
+            surprised: false,
+            hidden: false,
+            joins_round: None,
+        }
+    }
+
+    fn table() -> Vec<Character> {
+        vec![
+            creature(4, "Grug", "Orc"),
+            creature(7, "Orc 1", "Orc"),
+            creature(9, "Orc 2", "Orc"),
+            creature(2, "Ann", "Player"),
+            creature(3, "Annabel", "Player"),
+            creature(5, "Bob", "Ogre"),
+        ]
+    }
+
+    #[test]
+    fn find_creatures_by_list_number() {
+        let characters = table();
+        assert_eq!(find_creatures("1", &characters), vec![0]);
+        assert_eq!(find_creatures("6", &characters), vec![5]);
+        assert!(find_creatures("0", &characters).is_empty());
+        assert!(find_creatures("7", &characters).is_empty());
+    }
+
+    #[test]
+    fn find_creatures_by_id() {
+        let characters = table();
+        assert_eq!(find_creatures("#9", &characters), vec![2]);
+        assert_eq!(find_creatures("#2", &characters), vec![3]);
+        assert!(find_creatures("#1", &characters).is_empty());
+    }
+
+    #[test]
+    fn find_creatures_prefers_exact_names_over_prefixes() {
+        let characters = table();
+        assert_eq!(find_creatures("ann", &characters), vec![3]);
+        assert_eq!(find_creatures("anna", &characters), vec![4]);
+        assert_eq!(find_creatures("  GRUG ", &characters), vec![0]);
+    }
+
+    #[test]
+    fn find_creatures_returns_every_ambiguous_match() {
+        let characters = table();
+        assert_eq!(find_creatures("an", &characters), vec![3, 4]);
+        assert_eq!(find_creatures("orc", &characters), vec![1, 2]);
+    }
+
+    #[test]
+    fn find_creatures_falls_back_to_types() {
+        let characters = table();
+        assert_eq!(find_creatures("og", &characters), vec![5]);
+        assert!(find_creatures("dragon", &characters).is_empty());
+    }
+
+    #[test]
+    fn find_creatures_by_type_and_name() {
+        let characters = table();
+        assert_eq!(find_creatures("orc/grug", &characters), vec![0]);
+        assert_eq!(find_creatures("orc/orc", &characters), vec![1, 2]);
+        assert_eq!(find_creatures("pc/annab", &characters), vec![4]);
+        assert_eq!(find_creatures("player / ann", &characters), vec![3, 4]);
+        assert!(find_creatures("ogre/grug", &characters).is_empty());
+    }
 }
@@ -0,0 +1,393 @@
+use crate::user_input;
+use crate::encounter;
+use crate::encounter::Character;
+use crate::render;
+use crate::stat_search;
+use crate::stat_search::Creature;
+use crate::turn;
+use std::path::PathBuf;
+
+///
+/// Formats the encounter and statblocks can be exported to
+///
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Markdown,
+    Html,
+    Text,
+}
+
+impl Format {
+    ///
+    /// Parses a format name or file extension, such as "md", "html" or "text"
+    ///
+    pub fn parse(name: &str) -> Option<Format> {
+        match name.trim().to_lowercase().as_str() {
+            "m" | "md" | "markdown" => Some(Format::Markdown),
+            "h" | "htm" | "html" => Some(Format::Html),
+            "t" | "txt" | "text" | "plain" => Some(Format::Text),
+            _ => None,
+        }
+    }
+
+    ///
+    /// File extension used when exporting to this format
+    ///
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+            Format::Text => "txt",
+        }
+    }
+}
+
+///
+/// Pieces of an exported document, rendered differently by each format
+///
+enum Block {
+    Heading(usize, String),
+    Subtitle(String),
+    Paragraph(String),
+    Fields(Vec<(String, String)>),
+    Table(Vec<String>, Vec<Vec<String>>),
+    Entry(String, String),
+}
+
+///
+/// Width plain-text handouts are wrapped to, so they print cleanly
+///
+const TEXT_WIDTH: usize = 70;
+
+///
+/// Builds the blocks for a statblock, in the same order as the statblock browser
+///
+fn statblock_blocks(creature: &Creature, level: usize) -> Vec<Block> {
+    let mut blocks = vec![Block::Heading(level, creature.name.clone())];
+    if !creature.size.is_empty() {
+        blocks.push(Block::Subtitle(format!("{} {}", creature.size, creature.creature_type.to_lowercase())));
+    }
+    blocks.push(Block::Fields(vec![
+        ("Health".to_string(), creature.health.to_string()),
+        ("Armor class".to_string(), creature.armor_class.to_string()),
+        ("Initiative".to_string(), format!("{:+}", creature.initiative)),
+        ("Movement speed".to_string(), format!("{} ft.", creature.movement_speed)),
+        ("Challenge".to_string(), format!("{} ({} XP)", creature.challenge_rating, creature.xp)),
+    ]));
+
+    let scores = [creature.str, creature.dex, creature.con, creature.int, creature.wis, creature.cha];
+    blocks.push(Block::Table(
+        stat_search::ABILITIES.iter().map(|ability| ability.to_uppercase()).collect(),
+        vec![scores.iter().map(|score| format!("{} ({:+})", score, stat_search::ability_modifier(*score))).collect()],
+    ));
+
+    let mut fields = vec![("Proficiency bonus".to_string(), format!("+{}", creature.proficiency_bonus))];
+    let saves = stat_search::save_strings(creature);
+    if !saves.is_empty() {
+        fields.push(("Saving throws".to_string(), saves.join(", ")));
+    }
+    let skills = stat_search::skill_strings(creature);
+    if !skills.is_empty() {
+        fields.push(("Skills".to_string(), skills.join(", ")));
+    }
+    fields.push(("Passive Perception".to_string(), stat_search::passive_perception(creature).to_string()));
+    blocks.push(Block::Fields(fields));
+
+    blocks.push(Block::Heading(level + 1, "Actions".to_string()));
+    for action in &creature.actions {
+        let roll = match &action.save {
+            Some(save) => format!("DC {} {} saving throw{}", save.dc, save.ability.to_uppercase(), if save.half_on_success {", half damage on success"} else {""}),
+            None => format!("{:+} to hit", action.attack_modifier),
+        };
+        blocks.push(Block::Entry(action.name.clone(), format!("{} ({}, {}d{}+{} {} damage)", action.description, roll, action.damage_dice[0], action.damage_dice[1], action.damage_bonus, action.damage_type)));
+    }
+    if !creature.abilities.is_empty() {
+        blocks.push(Block::Heading(level + 1, "Abilities".to_string()));
+        for ability in &creature.abilities {
+            blocks.push(Block::Entry(ability.name.clone(), ability.description.clone()));
+        }
+    }
+    blocks
+}
+
+///
+/// Lists a creature's concentration and conditions for the initiative table, after any DM-only tags
+///
+fn effects(creature: &Character, tags: Vec<String>) -> String {
+    let mut effects = tags;
+    if let Some(spell) = &creature.concentration {
+        effects.push(format!("Concentrating: {}", spell));
+    }
+    effects.extend(creature.conditions.iter().map(|c| c.name.clone()));
+    effects.join(", ")
+}
+
+///
+/// Builds the player-safe initiative order, which only shows names, conditions and vague health descriptions for monsters.
+/// Hidden creatures and reinforcements that haven't arrived are left out
+///
+fn player_blocks(characters: &[Character], state: &turn::TurnState) -> Vec<Block> {
+    let rows = characters.iter().enumerate().filter(|(_, creature)| encounter::shown_to_players(creature, state.round)).map(|(index, creature)| {
+        vec![
+            if index + 1 == state.position {"▶".to_string()} else {String::new()},
+            creature.name.clone(),
+            if creature.character_type == "Player" {String::new()} else {encounter::health_band(creature).to_string()},
+            effects(creature, Vec::new()),
+        ]
+    }).collect();
+    vec![Block::Table(
+        ["Turn", "Creature", "Health", "Conditions"].iter().map(|header| header.to_string()).collect(),
+        rows,
+    )]
+}
+
+///
+/// Builds the blocks for the encounter's initiative order. The DM version adds a statblock for each kind of monster in it
+///
+fn encounter_blocks(characters: &[Character], state: &turn::TurnState, player: bool) -> Vec<Block> {
+    let mut blocks = vec![Block::Heading(1, "Encounter".to_string())];
+    // Players aren't told whose turn it is while a hidden creature acts
+    match characters.get(state.position.wrapping_sub(1)).filter(|creature| !player || encounter::shown_to_players(creature, state.round)) {
+        Some(creature) => blocks.push(Block::Paragraph(format!("Round {}, {}'s turn", state.round, creature.name))),
+        None => blocks.push(Block::Paragraph(format!("Round {}", state.round))),
+    }
+
+    if player {
+        blocks.push(Block::Heading(2, "Initiative".to_string()));
+        blocks.extend(player_blocks(characters, state));
+        return blocks;
+    }
+
+    let rows = characters.iter().enumerate().map(|(index, creature)| {
+        vec![
+            if index + 1 == state.position {"▶".to_string()} else {String::new()},
+            creature.initiative.to_string(),
+            if creature.character_type == "Player" {creature.name.clone()} else {format!("{}/{}", creature.character_type, creature.name)},
+            creature.ac.to_string(),
+            if creature.character_type == "Player" && !encounter::tracks_player_hp(creature.max_hp) {"-".to_string()} else {creature.hp.to_string()},
+            effects(creature, encounter::status_tags(creature, state.round)),
+        ]
+    }).collect();
+    blocks.push(Block::Heading(2, "Initiative".to_string()));
+    blocks.push(Block::Table(
+        ["Turn", "Init", "Creature", "AC", "HP", "Conditions"].iter().map(|header| header.to_string()).collect(),
+        rows,
+    ));
+
+    // Each kind of monster only needs its statblock once
+    let mut monster_types: Vec<&str> = Vec::new();
+    for creature in characters {
+        if creature.character_type != "Player" && !monster_types.contains(&creature.character_type.as_str()) {
+            monster_types.push(&creature.character_type);
+        }
+    }
+    if !monster_types.is_empty() {
+        blocks.push(Block::Heading(2, "Statblocks".to_string()));
+    }
+    for monster_type in monster_types {
+        if let Some(creature) = stat_search::find_statblock(monster_type) {
+            blocks.extend(statblock_blocks(&creature, 3));
+        }
+    }
+    blocks
+}
+
+///
+/// Escapes text for use inside HTML
+///
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+///
+/// Renders blocks as Markdown
+///
+fn to_markdown(blocks: &[Block]) -> String {
+    let mut markdown = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => markdown.push_str(&format!("{} {}\n\n", "#".repeat(*level), text)),
+            Block::Subtitle(text) => markdown.push_str(&format!("*{}*\n\n", text)),
+            Block::Paragraph(text) => markdown.push_str(&format!("{}\n\n", text)),
+            Block::Fields(fields) => {
+                for (label, value) in fields {
+                    markdown.push_str(&format!("- **{}:** {}\n", label, value));
+                }
+                markdown.push('\n');
+            },
+            Block::Table(headers, rows) => {
+                markdown.push_str(&format!("| {} |\n", headers.join(" | ")));
+                markdown.push_str(&format!("|{}\n", " --- |".repeat(headers.len())));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+                    markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+                }
+                markdown.push('\n');
+            },
+            Block::Entry(name, text) => markdown.push_str(&format!("***{}.*** {}\n\n", name, text)),
+        }
+    }
+    markdown
+}
+
+///
+/// Renders blocks as a standalone HTML page with its own styling
+///
+fn to_html(blocks: &[Block], title: &str) -> String {
+    let mut body = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => body.push_str(&format!("<h{level}>{}</h{level}>\n", escape_html(text), level = level)),
+            Block::Subtitle(text) => body.push_str(&format!("<p class=\"subtitle\">{}</p>\n", escape_html(text))),
+            Block::Paragraph(text) => body.push_str(&format!("<p>{}</p>\n", escape_html(text))),
+            Block::Fields(fields) => {
+                body.push_str("<ul class=\"fields\">\n");
+                for (label, value) in fields {
+                    body.push_str(&format!("<li><strong>{}:</strong> {}</li>\n", escape_html(label), escape_html(value)));
+                }
+                body.push_str("</ul>\n");
+            },
+            Block::Table(headers, rows) => {
+                body.push_str("<table>\n<tr>");
+                for header in headers {
+                    body.push_str(&format!("<th>{}</th>", escape_html(header)));
+                }
+                body.push_str("</tr>\n");
+                for row in rows {
+                    body.push_str("<tr>");
+                    for cell in row {
+                        body.push_str(&format!("<td>{}</td>", escape_html(cell)));
+                    }
+                    body.push_str("</tr>\n");
+                }
+                body.push_str("</table>\n");
+            },
+            Block::Entry(name, text) => body.push_str(&format!("<p><strong><em>{}.</em></strong> {}</p>\n", escape_html(name), escape_html(text))),
+        }
+    }
+
+    format!("<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<title>{}</title>
+<style>
+body {{ font-family: Georgia, serif; max-width: 50em; margin: 2em auto; padding: 0 1em; color: #222; }}
+h1, h2, h3 {{ color: #7a200d; }}
+h3 {{ border-bottom: 2px solid #7a200d; }}
+h4 {{ color: #7a200d; margin-bottom: 0.3em; }}
+.subtitle {{ font-style: italic; margin-top: -0.5em; }}
+.fields {{ list-style: none; padding: 0; }}
+table {{ border-collapse: collapse; margin: 0.5em 0; }}
+th, td {{ border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: center; }}
+@media print {{ body {{ margin: 0; }} }}
+</style>
+</head>
+<body>
+{}</body>
+</html>
+", escape_html(title), body)
+}
+
+///
+/// Renders blocks as plain text wrapped to [TEXT_WIDTH] columns, for printing
+///
+fn to_text(blocks: &[Block]) -> String {
+    let mut text = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, heading) => {
+                let underline = match level {1 => "=", 2 => "-", _ => ""};
+                if underline.is_empty() {
+                    text.push_str(&format!("{}:\n", heading));
+                } else {
+                    text.push_str(&format!("{}\n{}\n\n", heading, underline.repeat(render::visible_width(heading))));
+                }
+            },
+            Block::Subtitle(subtitle) => text.push_str(&format!("{}\n\n", subtitle)),
+            Block::Paragraph(paragraph) => {
+                for line in render::wrap(paragraph, TEXT_WIDTH) {
+                    text.push_str(&format!("{}\n", line));
+                }
+                text.push('\n');
+            },
+            Block::Fields(fields) => {
+                for (label, value) in fields {
+                    text.push_str(&format!("{}: {}\n", label, value));
+                }
+                text.push('\n');
+            },
+            Block::Table(headers, rows) => {
+                // Pads every column to its widest cell
+                let widths: Vec<usize> = (0..headers.len()).map(|column| {
+                    std::iter::once(&headers[column]).chain(rows.iter().map(|row| &row[column])).map(|cell| render::visible_width(cell)).max().unwrap_or(0)
+                }).collect();
+                for row in std::iter::once(headers).chain(rows.iter()) {
+                    let cells: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - render::visible_width(cell)))).collect();
+                    text.push_str(&format!("{}\n", cells.join("  ").trim_end()));
+                }
+                text.push('\n');
+            },
+            Block::Entry(name, entry) => {
+                for (index, line) in render::wrap(&format!("{}. {}", name, entry), TEXT_WIDTH - 2).iter().enumerate() {
+                    text.push_str(&format!("{}{}\n", if index == 0 {""} else {"  "}, line));
+                }
+                text.push('\n');
+            },
+        }
+    }
+    text
+}
+
+///
+/// Renders blocks in the chosen format
+///
+fn render_blocks(blocks: &[Block], format: Format, title: &str) -> String {
+    match format {
+        Format::Markdown => to_markdown(blocks),
+        Format::Html => to_html(blocks, title),
+        Format::Text => to_text(blocks),
+    }
+}
+
+///
+/// Exports the current encounter, either the DM version with statblocks or the player-safe version
+///
+pub fn encounter_document(format: Format, player: bool) -> String {
+    let characters = encounter::load_encounter_file();
+    let state = turn::load_turn();
+    render_blocks(&encounter_blocks(&characters, &state, player), format, "Encounter")
+}
+
+///
+/// Exports a single statblock, or None if the creature doesn't exist
+///
+pub fn statblock_document(creature_stat: &str, format: Format) -> Option<String> {
+    let creature = stat_search::find_statblock(creature_stat)?;
+    Some(render_blocks(&statblock_blocks(&creature, 1), format, &creature.name))
+}
+
+///
+/// Asks for a format and file, then exports the encounter to it. The player view exports the player-safe version
+///
+pub fn export_encounter(player: bool) {
+    println!("Export the encounter as (m)arkdown, (h)tml, or plain (t)ext?");
+    let format = match Format::parse(&user_input::input()) {
+        Some(format) => format,
+        None => {
+            println!("\nInvalid format!\n");
+            return;
+        }
+    };
+    let default = format!("~/.config/dnd-encounter-tracker/encounter.{}", format.extension());
+    println!("\nEnter a file to export to, or press enter for {}:", default);
+    let mut input = user_input::raw_input();
+    if input.is_empty() {
+        input = default;
+    }
+    let path = PathBuf::from(shellexpand::tilde(&input).into_owned());
+    match std::fs::write(&path, encounter_document(format, player)) {
+        Ok(..) => println!("\nEncounter exported to {}\n", input),
+        Err(e) => println!("\nCouldn't export the encounter: {}\n", e),
+    }
+}
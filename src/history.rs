@@ -0,0 +1,140 @@
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::path::PathBuf;
+use crate::encounter;
+use crate::encounter::Character;
+use crate::turn;
+use crate::turn::TurnState;
+use crate::log;
+use crate::log::EventKind;
+
+///
+/// Maximum number of snapshots kept in each of the undo and redo stacks
+///
+const MAX_HISTORY: usize = 100;
+
+///
+/// Snapshot struct used for storing the encounter and turn state as they were before a change
+///
+#[derive(Serialize, Deserialize, Debug)]
+struct Snapshot {
+    timestamp: String,
+    characters: Vec<Character>,
+    turn: TurnState,
+}
+
+///
+/// History struct used for storing the undo and redo stacks between runs
+///
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+///
+/// Reads the undo history, starting with empty stacks if it doesn't exist. Errors if the file can't be read or parsed,
+/// so it isn't saved over
+///
+fn read_history() -> Result<History, String> {
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker/history.json").into_owned();
+    let path = PathBuf::from(expanded_path);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(History::default()),
+        Err(e) => return Err(format!("{} couldn't be read ({})", path.display(), e)),
+    };
+    serde_json::from_str(&content).map_err(|e| format!("{} couldn't be read as undo history ({})", path.display(), e))
+}
+
+///
+/// Saves the undo history to ~/.config/dnd-encounter-tracker/history.json and creates the directory if it doesn't exist
+///
+fn save_history(history: &History) {
+    let json_history = serde_json::to_string(history).unwrap();
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker").into_owned();
+    let path = PathBuf::from(expanded_path);
+    std::fs::create_dir_all(&path).expect("Failed to create directory");
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker/history.json").into_owned();
+    let path = PathBuf::from(expanded_path);
+    std::fs::write(&path, json_history).expect("Unable to write to file");
+}
+
+///
+/// Takes a snapshot of the encounter and turn state currently on disk
+///
+fn current_snapshot() -> Snapshot {
+    Snapshot {
+        timestamp: chrono::Local::now().format("%H:%M:%S").to_string(),
+        characters: encounter::load_encounter_file(),
+        turn: turn::load_turn(),
+    }
+}
+
+///
+/// Writes a snapshot back to the encounter and turn files
+///
+fn restore(snapshot: Snapshot) {
+    encounter::write_encounter_file(&snapshot.characters);
+    turn::write_turn(&snapshot.turn);
+}
+
+///
+/// Pushes the state on disk onto the undo stack before it's replaced, skipping saves that don't change anything
+///
+pub fn record(characters: &[Character], state: &TurnState) {
+    let current = current_snapshot();
+    if current.turn == *state && serde_json::to_string(&current.characters).ok() == serde_json::to_string(characters).ok() {
+        return;
+    }
+
+    // An unreadable history is left alone, so this change just can't be undone
+    let mut history = match read_history() {
+        Ok(history) => history,
+        Err(e) => {
+            eprintln!("{}, so this change can't be undone", e);
+            return;
+        }
+    };
+    history.undo.push(current);
+    if history.undo.len() > MAX_HISTORY {
+        history.undo.remove(0);
+    }
+
+    // A new change makes anything that was undone unreachable
+    history.redo.clear();
+    save_history(&history);
+}
+
+///
+/// Reverts the last change, erroring if there's nothing to undo or the history can't be read
+///
+pub fn undo() -> Result<(), String> {
+    let mut history = read_history()?;
+    let snapshot = history.undo.pop().ok_or("Nothing to undo".to_string())?;
+    log::record(EventKind::Undo, &format!("Undid the change made after {}", snapshot.timestamp), None);
+    history.redo.push(current_snapshot());
+    restore(snapshot);
+    save_history(&history);
+    Ok(())
+}
+
+///
+/// Reapplies the last undone change, erroring if there's nothing to redo or the history can't be read
+///
+pub fn redo() -> Result<(), String> {
+    let mut history = read_history()?;
+    let snapshot = history.redo.pop().ok_or("Nothing to redo".to_string())?;
+    log::record(EventKind::Redo, &format!("Redid the change undone at {}", snapshot.timestamp), None);
+    history.undo.push(current_snapshot());
+    restore(snapshot);
+    save_history(&history);
+    Ok(())
+}
+
+///
+/// Empties both stacks, used when an encounter ends so it can't be undone into the next one
+///
+pub fn clear_history() {
+    save_history(&History::default());
+}
@@ -0,0 +1,437 @@
+use crate::user_input;
+use crate::render;
+use crate::stat_search;
+use crate::stat_search::{Ability, Action, ActionSave, Creature};
+use crate::validation;
+use titlecase::titlecase;
+use colored::*;
+
+///
+/// Sizes a creature can be, smallest first
+///
+const SIZES: [&str; 6] = ["Tiny", "Small", "Medium", "Large", "Huge", "Gargantuan"];
+
+///
+/// Challenge ratings with the XP and proficiency bonus that go with each one
+///
+pub const CHALLENGE_RATINGS: [(&str, i32, i32); 34] = [
+    ("0", 10, 2), ("1/8", 25, 2), ("1/4", 50, 2), ("1/2", 100, 2),
+    ("1", 200, 2), ("2", 450, 2), ("3", 700, 2), ("4", 1100, 2),
+    ("5", 1800, 3), ("6", 2300, 3), ("7", 2900, 3), ("8", 3900, 3),
+    ("9", 5000, 4), ("10", 5900, 4), ("11", 7200, 4), ("12", 8400, 4),
+    ("13", 10000, 5), ("14", 11500, 5), ("15", 13000, 5), ("16", 15000, 5),
+    ("17", 18000, 6), ("18", 20000, 6), ("19", 22000, 6), ("20", 25000, 6),
+    ("21", 33000, 7), ("22", 41000, 7), ("23", 50000, 7), ("24", 62000, 7),
+    ("25", 75000, 8), ("26", 90000, 8), ("27", 105000, 8), ("28", 120000, 8),
+    ("29", 135000, 9), ("30", 155000, 9),
+];
+
+///
+/// Asks for text, keeping the current value if nothing is entered
+///
+fn text_input(prompt: &str, current: &str) -> String {
+    if current.is_empty() {
+        println!("{}", prompt);
+    } else {
+        println!("{} (press enter to keep \"{}\")", prompt, current);
+    }
+    loop {
+        let input = user_input::raw_input();
+        if !input.is_empty() {
+            return input;
+        } else if !current.is_empty() {
+            return current.to_string();
+        }
+        println!("\nThis can't be empty:");
+    }
+}
+
+///
+/// Asks for a whole number, asking again until it's between min and max
+///
+fn bounded_input(prompt: &str, min: i32, max: i32) -> i32 {
+    println!("{}", prompt);
+    loop {
+        let number = user_input::int_input();
+        if number >= min && number <= max {
+            return number;
+        }
+        println!("\nPlease enter a number from {} to {}:", min, max);
+    }
+}
+
+///
+/// Asks for a size, asking again until it's one of [SIZES]
+///
+fn size_input() -> String {
+    println!("Enter size ({}):", SIZES.join(", "));
+    loop {
+        let input = user_input::input();
+        if let Some(size) = SIZES.iter().find(|size| size.to_lowercase() == input) {
+            return size.to_string();
+        }
+        println!("\nInvalid size! Enter one of {}:", SIZES.join(", "));
+    }
+}
+
+///
+/// Asks for a challenge rating, setting the creature's XP and proficiency bonus to match
+///
+fn challenge_input(creature: &mut Creature) {
+    println!("Enter challenge rating (e.g. 1/4, 2, 10):");
+    loop {
+        let input = user_input::input();
+        if let Some((rating, xp, proficiency)) = CHALLENGE_RATINGS.iter().find(|(rating, _, _)| *rating == input) {
+            creature.challenge_rating = rating.to_string();
+            creature.xp = *xp;
+            creature.proficiency_bonus = *proficiency;
+            return;
+        }
+        println!("\nInvalid challenge rating! Enter 0, 1/8, 1/4, 1/2, or a whole number up to 30:");
+    }
+}
+
+///
+/// Asks for all six ability scores
+///
+fn ability_scores_input(creature: &mut Creature) {
+    creature.str = bounded_input("Enter strength score:", 1, 30);
+    creature.dex = bounded_input("\nEnter dexterity score:", 1, 30);
+    creature.con = bounded_input("\nEnter constitution score:", 1, 30);
+    creature.int = bounded_input("\nEnter intelligence score:", 1, 30);
+    creature.wis = bounded_input("\nEnter wisdom score:", 1, 30);
+    creature.cha = bounded_input("\nEnter charisma score:", 1, 30);
+}
+
+///
+/// Asks for a comma separated list, asking again until every entry passes the check
+///
+fn list_input(prompt: &str, valid: fn(&str) -> bool) -> Vec<String> {
+    println!("{} Separate them with commas, or press enter for none:", prompt);
+    loop {
+        let input = user_input::input();
+        let entries: Vec<String> = input.split(',').map(|entry| entry.trim()).filter(|entry| !entry.is_empty()).map(titlecase).collect();
+        match entries.iter().find(|entry| !valid(entry)) {
+            Some(entry) => println!("\n{} isn't valid! Try again:", entry),
+            None => return entries,
+        }
+    }
+}
+
+///
+/// Asks for damage dice like "2d6", returning the number of dice and their sides
+///
+fn dice_input() -> Vec<i32> {
+    println!("Enter damage dice (e.g. 2d6):");
+    loop {
+        let input = user_input::input();
+        if let Some((count, sides)) = input.split_once('d') {
+            if let (Ok(count), Ok(sides)) = (count.parse::<i32>(), sides.parse::<i32>()) {
+                if count >= 1 && sides >= 1 {
+                    return vec![count, sides];
+                }
+            }
+        }
+        println!("\nInvalid dice! Enter them like 2d6:");
+    }
+}
+
+///
+/// Walks through creating an action, which either rolls to hit or forces a saving throw
+///
+fn action_input() -> Action {
+    let name = titlecase(&text_input("Enter action name:", ""));
+    let description = text_input("\nEnter action description:", "");
+    println!("\nDoes this action roll to (h)it or force a (s)aving throw?");
+    let kind = user_input::input();
+    println!();
+
+    let (attack_modifier, save) = if kind == "s" {
+        let ability = loop {
+            println!("Enter the saving throw ability (e.g. dex):");
+            match stat_search::ability_key(&user_input::input()) {
+                Some(ability) => break ability.to_string(),
+                None => println!("\nInvalid ability!"),
+            }
+        };
+        let dc = bounded_input("\nEnter the save DC:", 1, 30);
+        println!("\nDoes a successful save take half damage? (y/n)");
+        let half_on_success = user_input::input() == "y";
+        println!();
+        (0, Some(ActionSave { ability, dc, half_on_success }))
+    } else {
+        let modifier = bounded_input("Enter attack roll modifier:", -5, 30);
+        println!();
+        (modifier, None)
+    };
+
+    let damage_dice = dice_input();
+    let damage_bonus = bounded_input("\nEnter damage bonus:", -10, 50);
+    let damage_type = text_input("\nEnter damage type (e.g. slashing):", "").to_lowercase();
+    Action { name, description, attack_modifier, damage_dice, damage_bonus, damage_type, save }
+}
+
+///
+/// Menu used to add and remove a creature's actions or abilities, which are both lists of named entries
+///
+fn edit_list<T>(kind: &str, entries: &mut Vec<T>, name: fn(&T) -> &str, new: fn() -> T) {
+    loop {
+        println!("\n{}:", titlecase(kind));
+        if entries.is_empty() {
+            println!("None yet");
+        }
+        for (number, entry) in entries.iter().enumerate() {
+            println!("{}. {}", number + 1, name(entry));
+        }
+        println!("\n(a)dd, (r)emove, or press enter when done:");
+        match user_input::input().as_str() {
+            "a" => {
+                println!();
+                entries.push(new());
+            },
+            "r" => {
+                println!("\nEnter the number to remove:");
+                let number = user_input::usize_input();
+                if number >= 1 && number <= entries.len() {
+                    entries.remove(number - 1);
+                }
+            },
+            _ => break,
+        }
+    }
+}
+
+///
+/// Walks through creating an ability
+///
+fn ability_input() -> Ability {
+    let name = titlecase(&text_input("Enter ability name:", ""));
+    let description = text_input("\nEnter ability description:", "");
+    Ability { name, description }
+}
+
+///
+/// Prints every field of a creature being edited, numbered for selection
+///
+fn print_fields(creature: &Creature) {
+    let fields = vec![
+        format!("1. Name: {}", creature.name),
+        format!("2. Size: {}", creature.size),
+        format!("3. Type: {}", creature.creature_type),
+        format!("4. HP: {}", creature.health),
+        format!("5. AC: {}", creature.armor_class),
+        format!("6. Initiative bonus: {:+}", creature.initiative),
+        format!("7. Speed: {} ft.", creature.movement_speed),
+        format!("8. Challenge: {} ({} XP, proficiency +{})", creature.challenge_rating, creature.xp, creature.proficiency_bonus),
+        format!("9. STR {} DEX {} CON {} INT {} WIS {} CHA {}", creature.str, creature.dex, creature.con, creature.int, creature.wis, creature.cha),
+        format!("10. Saving throws: {}", if creature.saving_throws.is_empty() {"None".to_string()} else {creature.saving_throws.join(", ")}),
+        format!("11. Skills: {}", if creature.skills.is_empty() {"None".to_string()} else {creature.skills.join(", ")}),
+        format!("12. Actions: {}", creature.actions.len()),
+        format!("13. Abilities: {}", creature.abilities.len()),
+    ];
+    let title = format!("Homebrew {}", creature.name);
+    let width = render::fit_width(&[vec![title.clone()], fields.clone()].concat());
+    render::top(width);
+    render::centered(&title.bold().to_string(), width);
+    render::divider(width);
+    for (index, field) in fields.iter().enumerate() {
+        if index != 0 {
+            render::dotted(width);
+        }
+        render::centered(field, width);
+    }
+    render::bottom(width);
+}
+
+///
+/// Asks for a new value for one of the fields shown by [print_fields]
+///
+fn edit_field(creature: &mut Creature, field: usize) {
+    match field {
+        1 => {creature.name = titlecase(&text_input("Enter creature name:", &creature.name));},
+        2 => {creature.size = size_input();},
+        3 => {creature.creature_type = titlecase(&text_input("Enter creature type (e.g. humanoid, beast):", &creature.creature_type));},
+        4 => {creature.health = bounded_input("Enter average HP:", 1, 1000);},
+        5 => {creature.armor_class = bounded_input("Enter AC:", 1, 30);},
+        6 => {creature.initiative = bounded_input(&format!("Enter initiative bonus (usually the dexterity modifier, {:+}):", stat_search::ability_modifier(creature.dex)), -5, 15);},
+        7 => {creature.movement_speed = bounded_input("Enter walking speed in feet:", 0, 200);},
+        8 => {challenge_input(creature);},
+        9 => {ability_scores_input(creature);},
+        10 => {creature.saving_throws = list_input("Enter proficient saving throws (e.g. con, wis).", |save| stat_search::ability_key(save).is_some());},
+        11 => {creature.skills = list_input("Enter proficient skills (e.g. stealth, perception).", stat_search::is_skill);},
+        12 => {edit_list("actions", &mut creature.actions, |action| &action.name, action_input);},
+        13 => {edit_list("abilities", &mut creature.abilities, |ability| &ability.name, ability_input);},
+        _ => {println!("Invalid field!");}
+    }
+}
+
+///
+/// Lets the user edit fields until they save or discard the creature, returning it if it was saved
+///
+fn edit_statblock(mut creature: Creature, original_name: &str) -> Option<Creature> {
+    loop {
+        print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+        print_fields(&creature);
+        println!("\nEnter the number of a field to edit, (s)ave the creature, or type \"0\" to discard changes:");
+        let input = user_input::input();
+        println!();
+        match input.as_str() {
+            "s" => {
+                // Names have to stay unique, but a built-in creature can be overridden by editing it
+                let taken = stat_search::monster_names().iter().any(|name| name.to_lowercase() == creature.name.to_lowercase() && name.to_lowercase() != original_name.to_lowercase());
+                if taken {
+                    println!("There's already a creature called {}! Rename it before saving.", creature.name);
+                    println!("\nPress enter to continue:");
+                    user_input::input();
+                } else if !validation::validate_creature(&creature).is_empty() {
+                    println!("{} can't be saved yet:", creature.name);
+                    for (field, message) in validation::validate_creature(&creature) {
+                        println!("  {}: {}", field, message);
+                    }
+                    println!("\nPress enter to continue:");
+                    user_input::input();
+                } else {
+                    return Some(creature);
+                }
+            },
+            "0" | "done" => return None,
+            _ => match input.parse::<usize>() {
+                Ok(field) => edit_field(&mut creature, field),
+                Err(..) => println!("Invalid input!"),
+            },
+        }
+    }
+}
+
+///
+/// Walks through every field of a brand new creature, optionally copying an existing creature as a starting point
+///
+fn new_creature() -> Option<Creature> {
+    println!("Enter a creature to base this one on, or press enter to start from scratch:");
+    let base = user_input::input();
+    println!();
+    if !base.is_empty() {
+        let name = stat_search::pick_monster(&base)?;
+        let mut creature = stat_search::load_statblocks().into_iter().find(|c| c.name == name)?;
+        creature.name = format!("Homebrew {}", creature.name);
+        return Some(creature);
+    }
+
+    let mut creature = Creature {
+        name: titlecase(&text_input("Enter creature name:", "")),
+        size: String::new(),
+        creature_type: String::new(),
+        health: 1,
+        armor_class: 10,
+        initiative: 0,
+        movement_speed: 30,
+        challenge_rating: String::new(),
+        xp: 0,
+        str: 10,
+        dex: 10,
+        con: 10,
+        int: 10,
+        wis: 10,
+        cha: 10,
+        proficiency_bonus: 2,
+        saving_throws: Vec::new(),
+        skills: Vec::new(),
+        actions: Vec::new(),
+        abilities: Vec::new(),
+        loot: Vec::new(),
+    };
+    for field in 2..=13 {
+        println!();
+        edit_field(&mut creature, field);
+    }
+    Some(creature)
+}
+
+///
+/// Adds or replaces a creature in the homebrew file, removing the entry it was edited from
+///
+fn save_creature(creature: Creature, original_name: &str) {
+    let mut homebrew = match stat_search::read_homebrew() {
+        Ok(homebrew) => homebrew,
+        Err(e) => {
+            println!("{}\n\n{} wasn't saved", e, creature.name);
+            return;
+        }
+    };
+    homebrew.retain(|c| c.name.to_lowercase() != original_name.to_lowercase() && c.name.to_lowercase() != creature.name.to_lowercase());
+    println!("Saved {} to {}", creature.name, stat_search::homebrew_path().display());
+    homebrew.push(creature);
+    stat_search::save_homebrew(&homebrew);
+}
+
+///
+/// Menu used to create, edit and delete homebrew creatures, which are saved to ~/.config/dnd-encounter-tracker/homebrew.json
+///
+pub fn homebrew_menu() {
+    loop {
+        print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+        // Editing a homebrew file with problems would save over the entries that couldn't be read
+        let homebrew = match stat_search::read_homebrew() {
+            Ok(homebrew) => homebrew,
+            Err(e) => {
+                println!("{}\n\nFix or remove the file to edit homebrew creatures. Press enter to return:", e);
+                user_input::input();
+                return;
+            }
+        };
+        let names: Vec<String> = homebrew.iter().enumerate().map(|(number, creature)| format!("{}. {}", number + 1, creature.name)).collect();
+        let width = render::fit_width(&names);
+        render::top(width);
+        render::centered(&"Homebrew Creatures:".bold().to_string(), width);
+        render::divider(width);
+        if names.is_empty() {
+            render::centered("None yet", width);
+        }
+        for name in &names {
+            render::centered(name, width);
+        }
+        render::bottom(width);
+
+        println!("\n(c)reate a creature, (e)dit any creature, (d)elete a homebrew creature, or type \"done\" to return:");
+        let input = user_input::input();
+        println!();
+        match input.as_str() {
+            "c" => {
+                if let Some(creature) = new_creature().and_then(|creature| edit_statblock(creature, "")) {
+                    save_creature(creature, "");
+                    println!("\nPress enter to continue:");
+                    user_input::input();
+                }
+            },
+            "e" => {
+                println!("Enter the name of the creature to edit (built-in creatures are copied to your homebrew file):");
+                let query = user_input::input();
+                println!();
+                let name = match stat_search::pick_monster(&query) {
+                    Some(name) => name,
+                    None => continue,
+                };
+                let creature = stat_search::load_statblocks().into_iter().find(|c| c.name == name);
+                if let Some(creature) = creature.and_then(|creature| edit_statblock(creature, &name)) {
+                    save_creature(creature, &name);
+                    println!("\nPress enter to continue:");
+                    user_input::input();
+                }
+            },
+            "d" => {
+                println!("Enter the number of the homebrew creature to delete:");
+                let number = user_input::usize_input();
+                if number >= 1 && number <= homebrew.len() {
+                    let mut homebrew = homebrew;
+                    let creature = homebrew.remove(number - 1);
+                    stat_search::save_homebrew(&homebrew);
+                    println!("\nDeleted {}", creature.name);
+                    println!("\nPress enter to continue:");
+                    user_input::input();
+                }
+            },
+            "done" | "" => break,
+            _ => {},
+        }
+    }
+}
@@ -0,0 +1,760 @@
+use crate::user_input;
+use crate::encounter;
+use crate::encounter::Character;
+use crate::homebrew;
+use crate::stat_search;
+use crate::stat_search::{Ability, Action, ActionSave, Creature};
+use crate::validation;
+use serde_json::Value;
+use titlecase::titlecase;
+use colored::*;
+use std::fs;
+
+///
+/// Foundry's size codes and the sizes they stand for
+///
+const FOUNDRY_SIZES: [(&str, &str); 6] = [("tiny", "Tiny"), ("sm", "Small"), ("med", "Medium"), ("lg", "Large"), ("huge", "Huge"), ("grg", "Gargantuan")];
+
+///
+/// Foundry's skill codes and the skills they stand for
+///
+const FOUNDRY_SKILLS: [(&str, &str); 18] = [
+    ("acr", "acrobatics"), ("ani", "animal handling"), ("arc", "arcana"), ("ath", "athletics"),
+    ("dec", "deception"), ("his", "history"), ("ins", "insight"), ("itm", "intimidation"),
+    ("inv", "investigation"), ("med", "medicine"), ("nat", "nature"), ("prc", "perception"),
+    ("prf", "performance"), ("per", "persuasion"), ("rel", "religion"), ("slt", "sleight of hand"),
+    ("ste", "stealth"), ("sur", "survival"),
+];
+
+///
+/// Roll20's ability attribute names, in the same order as [stat_search::ABILITIES]
+///
+const ROLL20_ABILITIES: [&str; 6] = ["strength", "dexterity", "constitution", "intelligence", "wisdom", "charisma"];
+
+///
+/// Import struct used for collecting everything read from VTT exports before it's added to the tracker
+///
+#[derive(Default)]
+pub struct Import {
+    pub statblocks: Vec<Creature>,
+    pub combatants: Vec<Character>,
+    pub unsupported: Vec<String>,
+}
+
+///
+/// Actor struct used for an exported creature or character, before it's split into a statblock and a combatant
+///
+struct Actor {
+    id: String,
+    creature: Creature,
+    player: bool,
+    hp: i32,
+    level: i32,
+}
+
+///
+/// Reads a number that might have been exported as a string, like most Roll20 attributes
+///
+fn number(value: Option<&Value>) -> Option<i32> {
+    match value? {
+        Value::Number(number) => number.as_f64().map(|number| number as i32),
+        Value::String(text) => text.trim().trim_start_matches('+').parse().ok(),
+        _ => None,
+    }
+}
+
+///
+/// Reads a string, treating anything missing as empty
+///
+fn text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.trim().to_string(),
+        Some(Value::Number(number)) => number.to_string(),
+        _ => String::new(),
+    }
+}
+
+///
+/// Removes the HTML tags VTTs keep in descriptions
+///
+fn strip_html(html: &str) -> String {
+    let mut plain = String::new();
+    let mut in_tag = false;
+    for character in html.chars() {
+        match character {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                plain.push(' ');
+            },
+            _ if !in_tag => plain.push(character),
+            _ => {},
+        }
+    }
+    plain.replace("&nbsp;", " ").replace("&amp;", "&").split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+///
+/// Parses a damage formula like "1d12 + @mod" or "2d6+3" into its dice count, sides and flat bonus.
+/// Returns None for anything more involved, such as several kinds of dice
+///
+fn parse_damage(formula: &str, modifier: i32) -> Option<(i32, i32, i32)> {
+    // Drops flavor text like "1d6[fire]" and fills in the ability modifier
+    let mut cleaned = String::new();
+    let mut in_flavor = false;
+    for character in formula.chars() {
+        match character {
+            '[' => in_flavor = true,
+            ']' => in_flavor = false,
+            _ if !in_flavor && !character.is_whitespace() => cleaned.push(character),
+            _ => {},
+        }
+    }
+    let cleaned = cleaned.to_lowercase().replace("@mod", &format!("{:+}", modifier)).replace("+-", "-").replace("++", "+");
+
+    let end = cleaned.find(['+', '-']).unwrap_or(cleaned.len());
+    let (dice, mut rest) = cleaned.split_at(end);
+    let (count, sides) = dice.split_once('d')?;
+    let count = if count.is_empty() {1} else {count.parse().ok()?};
+    let sides = sides.parse().ok()?;
+
+    let mut bonus = 0;
+    while !rest.is_empty() {
+        let sign = if rest.starts_with('-') {-1} else {1};
+        rest = &rest[1..];
+        let end = rest.find(['+', '-']).unwrap_or(rest.len());
+        bonus += sign * rest[..end].parse::<i32>().ok()?;
+        rest = &rest[end..];
+    }
+    Some((count, sides, bonus))
+}
+
+///
+/// Formats an exported challenge rating, which Foundry stores as a decimal (e.g. 0.25 for 1/4)
+///
+fn challenge_rating(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::Number(number)) => match number.as_f64().unwrap_or(0.0) {
+            0.125 => "1/8".to_string(),
+            0.25 => "1/4".to_string(),
+            0.5 => "1/2".to_string(),
+            rating => (rating as i32).to_string(),
+        },
+        value => text(value),
+    }
+}
+
+///
+/// Builds a creature with every stat at a neutral value, to be filled in from an export
+///
+fn blank_creature(name: &str) -> Creature {
+    Creature {
+        name: titlecase(name),
+        size: String::new(),
+        creature_type: String::new(),
+        health: 1,
+        armor_class: 10,
+        initiative: 0,
+        movement_speed: 30,
+        challenge_rating: String::new(),
+        xp: 0,
+        str: 10,
+        dex: 10,
+        con: 10,
+        int: 10,
+        wis: 10,
+        cha: 10,
+        proficiency_bonus: 2,
+        saving_throws: Vec::new(),
+        skills: Vec::new(),
+        actions: Vec::new(),
+        abilities: Vec::new(),
+        loot: Vec::new(),
+    }
+}
+
+///
+/// Fills in XP and proficiency from the challenge rating where the export left them out
+///
+fn apply_challenge_rating(creature: &mut Creature, proficiency: Option<i32>) {
+    if let Some((_, xp, bonus)) = homebrew::CHALLENGE_RATINGS.iter().find(|(rating, _, _)| *rating == creature.challenge_rating) {
+        if creature.xp == 0 {
+            creature.xp = *xp;
+        }
+        creature.proficiency_bonus = *bonus;
+    }
+    if let Some(proficiency) = proficiency {
+        creature.proficiency_bonus = proficiency;
+    }
+}
+
+///
+/// Gets a creature's modifier for an ability key
+///
+fn modifier(creature: &Creature, key: &str) -> i32 {
+    let score = match key {
+        "str" => creature.str,
+        "dex" => creature.dex,
+        "con" => creature.con,
+        "int" => creature.int,
+        "wis" => creature.wis,
+        _ => creature.cha,
+    };
+    stat_search::ability_modifier(score)
+}
+
+///
+/// Converts a Foundry item with damage into an action, noting anything about it that can't be brought over
+///
+fn foundry_action(item: &Value, system: &Value, creature: &Creature, unsupported: &mut Vec<String>) -> Option<Action> {
+    let name = text(item.get("name"));
+    let label = format!("{}: {}", creature.name, name);
+
+    // Damage is a list of [formula, type] parts in older systems, and a base roll in newer ones
+    let mut parts: Vec<(String, String)> = system.pointer("/damage/parts").and_then(|parts| parts.as_array()).map(|parts| {
+        parts.iter().map(|part| (text(part.get(0)), text(part.get(1)))).collect()
+    }).unwrap_or_default();
+    if let Some(base) = system.pointer("/damage/base").filter(|base| number(base.get("number")).is_some()) {
+        let formula = format!("{}d{}{}", text(base.get("number")), text(base.get("denomination")), match text(base.get("bonus")) {
+            bonus if bonus.is_empty() => String::new(),
+            bonus => format!("+{}", bonus),
+        });
+        let damage_type = base.get("types").and_then(|types| types.as_array()).map(|types| text(types.first())).unwrap_or_default();
+        parts.insert(0, (formula, damage_type));
+    }
+    let (formula, damage_type) = parts.first().cloned()?;
+    if parts.len() > 1 {
+        unsupported.push(format!("{} deals more than one type of damage, so only the {} damage was kept", label, damage_type));
+    }
+
+    // Finesse and ranged weapons use Dexterity when it's higher, everything else defaults to Strength
+    let properties = system.get("properties");
+    let finesse = match properties {
+        Some(Value::Array(properties)) => properties.iter().any(|property| property == "fin"),
+        Some(Value::Object(properties)) => properties.get("fin") == Some(&Value::Bool(true)),
+        _ => false,
+    };
+    let ranged = text(system.get("actionType")).starts_with('r');
+    let ability = match stat_search::ability_key(&text(system.get("ability"))) {
+        Some(ability) => ability,
+        None if (finesse || ranged) && creature.dex > creature.str => "dex",
+        None => "str",
+    };
+    let ability_modifier = modifier(creature, ability);
+
+    let (count, sides, bonus) = match parse_damage(&formula, ability_modifier) {
+        Some(damage) => damage,
+        None => {
+            unsupported.push(format!("{} has damage \"{}\", which isn't simple dice, so it was skipped", label, formula));
+            return None;
+        }
+    };
+
+    let save = match stat_search::ability_key(&text(system.pointer("/save/ability"))) {
+        Some(save_ability) => {
+            let dc = number(system.pointer("/save/dc")).unwrap_or(8 + creature.proficiency_bonus + ability_modifier);
+            Some(ActionSave { ability: titlecase(save_ability), dc, half_on_success: false })
+        },
+        None => None,
+    };
+
+    Some(Action {
+        name: titlecase(&name),
+        description: strip_html(&text(system.pointer("/description/value"))),
+        attack_modifier: ability_modifier + creature.proficiency_bonus + number(system.get("attackBonus")).unwrap_or(0),
+        damage_dice: vec![count, sides],
+        damage_bonus: bonus,
+        damage_type: damage_type.to_lowercase(),
+        save,
+    })
+}
+
+///
+/// Reads a Foundry VTT (D&D 5e system) actor export
+///
+fn foundry_actor(actor: &Value, unsupported: &mut Vec<String>) -> Option<Actor> {
+    // Newer versions keep the stats under "system", older ones under "data"
+    let system = actor.get("system").or_else(|| actor.get("data"))?;
+    let mut creature = blank_creature(&text(actor.get("name")));
+    let player = text(actor.get("type")) == "character";
+
+    let scores: Vec<i32> = stat_search::ABILITIES.iter().map(|key| number(system.pointer(&format!("/abilities/{}/value", key))).unwrap_or(10)).collect();
+    [creature.str, creature.dex, creature.con, creature.int, creature.wis, creature.cha] = [scores[0], scores[1], scores[2], scores[3], scores[4], scores[5]];
+    for key in stat_search::ABILITIES {
+        if number(system.pointer(&format!("/abilities/{}/proficient", key))).unwrap_or(0) >= 1 {
+            creature.saving_throws.push(titlecase(key));
+        }
+    }
+    for (code, skill) in FOUNDRY_SKILLS {
+        if number(system.pointer(&format!("/skills/{}/value", code))).unwrap_or(0) >= 1 {
+            creature.skills.push(titlecase(skill));
+        }
+    }
+
+    creature.health = number(system.pointer("/attributes/hp/max")).unwrap_or(1);
+    let hp = number(system.pointer("/attributes/hp/value")).unwrap_or(creature.health);
+    creature.armor_class = match number(system.pointer("/attributes/ac/flat")).or_else(|| number(system.pointer("/attributes/ac/value"))) {
+        Some(ac) => ac,
+        None => {
+            unsupported.push(format!("{}: AC is worked out from equipment in Foundry, so 10 + Dex was used", creature.name));
+            10 + stat_search::ability_modifier(creature.dex)
+        }
+    };
+    creature.initiative = stat_search::ability_modifier(creature.dex) + number(system.pointer("/attributes/init/bonus")).unwrap_or(0);
+    creature.movement_speed = number(system.pointer("/attributes/movement/walk")).unwrap_or(30);
+    creature.challenge_rating = challenge_rating(system.pointer("/details/cr"));
+    creature.xp = number(system.pointer("/details/xp/value")).unwrap_or(0);
+    apply_challenge_rating(&mut creature, number(system.pointer("/attributes/prof")));
+
+    // The creature type is a plain string in older systems
+    let creature_type = system.pointer("/details/type/value").or_else(|| system.pointer("/details/type"));
+    creature.creature_type = titlecase(&text(creature_type));
+    let size = text(system.pointer("/traits/size"));
+    creature.size = FOUNDRY_SIZES.iter().find(|(code, _)| *code == size).map(|(_, size)| size.to_string()).unwrap_or_default();
+
+    // Player levels come from their class items, or the details of older exports
+    let mut class_levels = 0;
+    let mut spells = Vec::new();
+    for item in actor.get("items").and_then(|items| items.as_array()).into_iter().flatten() {
+        let item_system = item.get("system").or_else(|| item.get("data")).unwrap_or(&Value::Null);
+        match text(item.get("type")).as_str() {
+            "class" => class_levels += number(item_system.get("levels")).unwrap_or(0),
+            "spell" => spells.push(text(item.get("name"))),
+            "weapon" | "feat" => match foundry_action(item, item_system, &creature, unsupported) {
+                Some(action) => creature.actions.push(action),
+                None if text(item.get("type")) == "feat" => creature.abilities.push(Ability {
+                    name: titlecase(&text(item.get("name"))),
+                    description: strip_html(&text(item_system.pointer("/description/value"))),
+                }),
+                None => {},
+            },
+            _ => {},
+        }
+    }
+    if !player {
+        note_foundry_extras(system, &creature.name, &spells, unsupported);
+    }
+    let level = if class_levels > 0 {class_levels} else {number(system.pointer("/details/level")).unwrap_or(0)};
+
+    Some(Actor { id: text(actor.get("_id")), creature, player, hp, level })
+}
+
+///
+/// Lists the parts of a Foundry monster the tracker has nowhere to keep
+///
+fn note_foundry_extras(system: &Value, name: &str, spells: &[String], unsupported: &mut Vec<String>) {
+    if !spells.is_empty() {
+        unsupported.push(format!("{}: spells aren't supported ({})", name, spells.join(", ")));
+    }
+    if number(system.pointer("/resources/legact/max")).unwrap_or(0) > 0 {
+        unsupported.push(format!("{}: legendary actions aren't supported", name));
+    }
+    let traits = ["dr", "di", "dv", "ci"].iter().any(|key| {
+        system.pointer(&format!("/traits/{}/value", key)).and_then(|value| value.as_array()).is_some_and(|value| !value.is_empty())
+    });
+    if traits {
+        unsupported.push(format!("{}: damage resistances, immunities, vulnerabilities and condition immunities aren't supported", name));
+    }
+}
+
+///
+/// Finds a Roll20 attribute by name
+///
+fn roll20_attribute<'a>(character: &'a Value, name: &str) -> Option<&'a Value> {
+    character.get("attribs")?.as_array()?.iter().find(|attribute| text(attribute.get("name")) == name)
+}
+
+///
+/// Gets the current value of a Roll20 attribute
+///
+fn roll20_current(character: &Value, name: &str) -> String {
+    roll20_attribute(character, name).map(|attribute| text(attribute.get("current"))).unwrap_or_default()
+}
+
+///
+/// Groups Roll20 repeating attributes (e.g. repeating_npcaction_<row>_name) by row, keeping the order they were exported in
+///
+fn roll20_rows(character: &Value, prefix: &str) -> Vec<Vec<(String, String)>> {
+    let mut ids: Vec<String> = Vec::new();
+    let mut rows: Vec<Vec<(String, String)>> = Vec::new();
+    for attribute in character.get("attribs").and_then(|attribs| attribs.as_array()).into_iter().flatten() {
+        let name = text(attribute.get("name"));
+        let Some((id, field)) = name.strip_prefix(prefix).and_then(|rest| rest.split_once('_')) else {continue};
+        let row = match ids.iter().position(|existing| existing == id) {
+            Some(row) => row,
+            None => {
+                ids.push(id.to_string());
+                rows.push(Vec::new());
+                rows.len() - 1
+            }
+        };
+        rows[row].push((field.to_string(), text(attribute.get("current"))));
+    }
+    rows
+}
+
+///
+/// Gets a field from a Roll20 repeating row
+///
+fn row_field(row: &[(String, String)], field: &str) -> String {
+    row.iter().find(|(name, _)| name == field).map(|(_, value)| value.clone()).unwrap_or_default()
+}
+
+///
+/// Reads a Roll20 character exported from the D&D 5e by Roll20 sheet
+///
+fn roll20_character(character: &Value, unsupported: &mut Vec<String>) -> Option<Actor> {
+    let mut creature = blank_creature(&text(character.get("name")));
+    if creature.name.is_empty() {
+        return None;
+    }
+    let player = roll20_current(character, "npc") != "1";
+    let attribute = |name: &str| number(roll20_attribute(character, name).and_then(|attribute| attribute.get("current")));
+
+    let scores: Vec<i32> = ROLL20_ABILITIES.iter().map(|ability| attribute(ability).unwrap_or(10)).collect();
+    [creature.str, creature.dex, creature.con, creature.int, creature.wis, creature.cha] = [scores[0], scores[1], scores[2], scores[3], scores[4], scores[5]];
+    for key in stat_search::ABILITIES {
+        if !roll20_current(character, &format!("npc_{}_save", key)).is_empty() {
+            creature.saving_throws.push(titlecase(key));
+        }
+    }
+    for (skill, _) in stat_search::SKILLS {
+        if !roll20_current(character, &format!("npc_{}", skill.replace(' ', "_"))).is_empty() {
+            creature.skills.push(titlecase(skill));
+        }
+    }
+
+    let hp = roll20_attribute(character, "hp");
+    creature.health = number(hp.and_then(|hp| hp.get("max"))).or_else(|| number(hp.and_then(|hp| hp.get("current")))).unwrap_or(1);
+    let current_hp = number(hp.and_then(|hp| hp.get("current"))).unwrap_or(creature.health);
+    creature.armor_class = attribute(if player {"ac"} else {"npc_ac"}).unwrap_or(10 + stat_search::ability_modifier(creature.dex));
+    creature.initiative = attribute("initiative_bonus").unwrap_or(stat_search::ability_modifier(creature.dex));
+    let speed = roll20_current(character, if player {"speed"} else {"npc_speed"});
+    creature.movement_speed = speed.split(|c: char| !c.is_ascii_digit()).find(|part| !part.is_empty()).and_then(|speed| speed.parse().ok()).unwrap_or(30);
+    creature.challenge_rating = roll20_current(character, "npc_challenge");
+    creature.xp = attribute("npc_xp").unwrap_or(0);
+    apply_challenge_rating(&mut creature, attribute("npc_pb"));
+
+    // NPC types read like "Medium humanoid (orc), chaotic evil"
+    let npc_type = roll20_current(character, "npc_type");
+    let mut words = npc_type.split([' ', ',']).filter(|word| !word.is_empty());
+    creature.size = titlecase(words.next().unwrap_or_default());
+    creature.creature_type = titlecase(words.next().unwrap_or_default());
+
+    for row in roll20_rows(character, "repeating_npcaction_") {
+        let name = titlecase(&row_field(&row, "name"));
+        let formula = row_field(&row, "attack_damage");
+        if formula.is_empty() {
+            creature.abilities.push(Ability { name, description: row_field(&row, "description") });
+            continue;
+        }
+        match parse_damage(&formula, 0) {
+            Some((count, sides, bonus)) => {
+                if !row_field(&row, "attack_damage2").is_empty() {
+                    unsupported.push(format!("{}: {} deals more than one type of damage, so only the {} damage was kept", creature.name, name, row_field(&row, "attack_damagetype")));
+                }
+                creature.actions.push(Action {
+                    name,
+                    description: row_field(&row, "description"),
+                    attack_modifier: row_field(&row, "attack_tohit").trim_start_matches('+').parse().unwrap_or(0),
+                    damage_dice: vec![count, sides],
+                    damage_bonus: bonus,
+                    damage_type: row_field(&row, "attack_damagetype").to_lowercase(),
+                    save: None,
+                });
+            },
+            None => unsupported.push(format!("{}: {} has damage \"{}\", which isn't simple dice, so it was skipped", creature.name, name, formula)),
+        }
+    }
+    for row in roll20_rows(character, "repeating_npctrait_") {
+        creature.abilities.push(Ability { name: titlecase(&row_field(&row, "name")), description: row_field(&row, "description") });
+    }
+    if !player {
+        if !roll20_rows(character, "repeating_npcaction-l_").is_empty() {
+            unsupported.push(format!("{}: legendary actions aren't supported", creature.name));
+        }
+        let spells: Vec<String> = ["cantrip", "1", "2", "3", "4", "5", "6", "7", "8", "9"].iter()
+            .flat_map(|level| roll20_rows(character, &format!("repeating_spell-{}_", level)))
+            .map(|row| row_field(&row, "spellname"))
+            .filter(|name| !name.is_empty())
+            .collect();
+        if !spells.is_empty() {
+            unsupported.push(format!("{}: spells aren't supported ({})", creature.name, spells.join(", ")));
+        }
+        let traits = ["npc_resistances", "npc_immunities", "npc_vulnerabilities", "npc_condition_immunities"].iter().any(|name| !roll20_current(character, name).is_empty());
+        if traits {
+            unsupported.push(format!("{}: damage resistances, immunities, vulnerabilities and condition immunities aren't supported", creature.name));
+        }
+    }
+
+    Some(Actor { id: text(character.get("id")), creature, player, hp: current_hp, level: attribute("level").unwrap_or(0) })
+}
+
+///
+/// Rolls initiative for an actor that doesn't have one yet, keeping it above 0 like [stat_search::create_monster]
+///
+fn roll_initiative(actor: &Actor) -> i32 {
+    stat_search::roll_initiative(&actor.creature)
+}
+
+///
+/// Turns an actor into a combatant. Monsters point at their imported statblock, and players keep the usual placeholder HP
+///
+fn combatant(actor: &Actor, name: &str, initiative: i32) -> Character {
+    let (character_type, hp, max_hp) = if actor.player {
+        ("Player".to_string(), encounter::PLAYER_HP, encounter::PLAYER_HP)
+    } else {
+        (actor.creature.name.clone(), actor.hp, actor.creature.health)
+    };
+    Character {
+        id: 0,
+        name: titlecase(name),
+        character_type,
+        ac: actor.creature.armor_class,
+        hp,
+        max_hp,
+        initiative,
+        level: if actor.player {actor.level} else {0},
+        concentration: None,
+        conditions: Vec::new(),
+        surprised: false,
+        hidden: false,
+        joins_round: None,
+    }
+}
+
+///
+/// Finds the index of the actor a combatant refers to, by its ID and then by name
+///
+fn find_actor(actors: &[Actor], id: &str, name: &str) -> Option<usize> {
+    actors.iter().position(|actor| !id.is_empty() && actor.id == id)
+        .or_else(|| actors.iter().position(|actor| actor.creature.name.to_lowercase() == name.to_lowercase()))
+}
+
+///
+/// Adds the combatants of a Foundry combat export, noting which actors were used in matched
+///
+fn foundry_combat(combat: &Value, actors: &[Actor], matched: &mut Vec<usize>, import: &mut Import) {
+    for entry in combat.get("combatants").and_then(|combatants| combatants.as_array()).into_iter().flatten() {
+        let name = text(entry.get("name"));
+        let Some(index) = find_actor(actors, &text(entry.get("actorId")), &name) else {
+            import.unsupported.push(format!("{}: the actor wasn't in the imported files, so they were skipped. Export them from Foundry too", if name.is_empty() {"A combatant"} else {&name}));
+            continue;
+        };
+        let actor = &actors[index];
+        matched.push(index);
+        let name = if name.is_empty() {actor.creature.name.clone()} else {name};
+        let initiative = number(entry.get("initiative")).unwrap_or_else(|| roll_initiative(actor));
+        import.combatants.push(combatant(actor, &name, initiative));
+    }
+}
+
+///
+/// Adds the combatants of a Roll20 campaign's turn order, finding who each token represents and noting them in matched
+///
+fn roll20_turn_order(campaign: &Value, actors: &[Actor], matched: &mut Vec<usize>, import: &mut Import) {
+    // The turn order is stored as a JSON string inside the campaign
+    let turn_order = match campaign.get("turnorder") {
+        Some(Value::String(order)) => serde_json::from_str(order).unwrap_or(Value::Null),
+        Some(order) => order.clone(),
+        None => return,
+    };
+    let graphics: Vec<&Value> = campaign.get("pages").and_then(|pages| pages.as_array()).into_iter().flatten()
+        .flat_map(|page| page.get("graphics").and_then(|graphics| graphics.as_array()).into_iter().flatten())
+        .collect();
+
+    for entry in turn_order.as_array().into_iter().flatten() {
+        let custom = text(entry.get("custom"));
+        let token = graphics.iter().find(|graphic| text(graphic.get("id")) == text(entry.get("id")));
+        let character_id = token.map(|token| text(token.get("represents"))).unwrap_or_default();
+        let name = token.map(|token| text(token.get("name"))).filter(|name| !name.is_empty()).unwrap_or(custom.clone());
+        let Some(index) = find_actor(actors, &character_id, &name) else {
+            import.unsupported.push(format!("{}: this turn order entry isn't linked to an imported character, so it was skipped", if name.is_empty() {"An entry"} else {&name}));
+            continue;
+        };
+        let actor = &actors[index];
+        matched.push(index);
+        let name = if name.is_empty() {actor.creature.name.clone()} else {name};
+        let initiative = number(entry.get("pr")).unwrap_or_else(|| roll_initiative(actor));
+        import.combatants.push(combatant(actor, &name, initiative));
+    }
+}
+
+///
+/// Reads actors from a parsed export, returning any combats to resolve once every file's actors are known
+///
+fn read_actors(value: &Value, path: &str, actors: &mut Vec<Actor>, combats: &mut Vec<Value>, unsupported: &mut Vec<String>) {
+    match value {
+        Value::Array(entries) => {
+            for entry in entries {
+                read_actors(entry, path, actors, combats, unsupported);
+            }
+        },
+        _ if value.get("combatants").is_some() => combats.push(value.clone()),
+        _ if value.get("attribs").is_some() => actors.extend(roll20_character(value, unsupported)),
+        _ if value.get("characters").is_some() => {
+            for character in value["characters"].as_array().into_iter().flatten() {
+                actors.extend(roll20_character(character, unsupported));
+            }
+            if value.get("turnorder").is_some() {
+                combats.push(value.clone());
+            }
+        },
+        _ if value.get("type").is_some() && (value.get("system").is_some() || value.get("data").is_some()) => {
+            actors.extend(foundry_actor(value, unsupported));
+        },
+        _ => unsupported.push(format!("{}: found something that isn't a Foundry actor or combat, or a Roll20 character or campaign", path)),
+    }
+}
+
+///
+/// Reads Foundry VTT actor and combat exports and Roll20 character and campaign exports.
+/// Monsters become statblocks, and combatants come from combats and turn orders, plus every actor that isn't in one when
+/// add_actors is set
+///
+pub fn read_exports(paths: &[String], add_actors: bool) -> Result<Import, String> {
+    let mut import = Import::default();
+    let mut actors = Vec::new();
+    let mut combats = Vec::new();
+    let mut matched = Vec::new();
+    for path in paths {
+        let path = shellexpand::tilde(path).into_owned();
+        let contents = fs::read_to_string(&path).map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+        let value: Value = serde_json::from_str(&contents).map_err(|e| format!("Couldn't parse {}: {}", path, e))?;
+        read_actors(&value, &path, &mut actors, &mut combats, &mut import.unsupported);
+    }
+
+    for combat in &combats {
+        if combat.get("combatants").is_some() {
+            foundry_combat(combat, &actors, &mut matched, &mut import);
+        } else {
+            roll20_turn_order(combat, &actors, &mut matched, &mut import);
+        }
+    }
+    if add_actors {
+        for (_, actor) in actors.iter().enumerate().filter(|(index, _)| !matched.contains(index)) {
+            import.combatants.push(combatant(actor, &actor.creature.name, roll_initiative(actor)));
+        }
+    }
+
+    // Actors that appear in several files only need one statblock
+    for actor in actors.into_iter().filter(|actor| !actor.player) {
+        import.statblocks.retain(|creature: &Creature| creature.name.to_lowercase() != actor.creature.name.to_lowercase());
+        import.statblocks.push(actor.creature);
+    }
+    Ok(import)
+}
+
+///
+/// Saves the imported statblocks to the homebrew library and adds the combatants to the encounter.
+/// Returns a line for each thing that was done, adding anything that had to be skipped to the unsupported list
+///
+pub fn apply(import: &mut Import) -> Vec<String> {
+    let mut done = Vec::new();
+    let mut skipped = Vec::new();
+    let built_in: Vec<String> = stat_search::monster_names().into_iter().map(|name| name.to_lowercase()).collect();
+    // A homebrew file with problems isn't saved over, since the entries that couldn't be read would be lost
+    let mut homebrew = match stat_search::read_homebrew() {
+        Ok(homebrew) => homebrew,
+        Err(..) if import.statblocks.is_empty() => Vec::new(),
+        Err(e) => {
+            for creature in &import.statblocks {
+                import.unsupported.push(format!("{}: the statblock wasn't saved because the homebrew file has problems", creature.name));
+                skipped.push(creature.name.to_lowercase());
+            }
+            import.unsupported.push(e);
+            import.statblocks.clear();
+            Vec::new()
+        }
+    };
+    for creature in &import.statblocks {
+        let problems = validation::validate_creature(creature);
+        if !problems.is_empty() {
+            let problems: Vec<String> = problems.iter().map(|(field, message)| format!("{}: {}", field, message)).collect();
+            import.unsupported.push(format!("{}: the statblock wasn't saved because it's invalid ({})", creature.name, problems.join("; ")));
+            skipped.push(creature.name.to_lowercase());
+            continue;
+        }
+        match homebrew.iter().position(|existing| existing.name.to_lowercase() == creature.name.to_lowercase()) {
+            Some(index) => {
+                homebrew[index] = creature.clone();
+                done.push(format!("Updated the {} statblock", creature.name));
+            },
+            None => {
+                if built_in.contains(&creature.name.to_lowercase()) {
+                    done.push(format!("Saved the {} statblock, which replaces the built-in one", creature.name));
+                } else {
+                    done.push(format!("Saved the {} statblock", creature.name));
+                }
+                homebrew.push(creature.clone());
+            }
+        }
+    }
+    if !done.is_empty() {
+        stat_search::save_homebrew(&homebrew);
+    }
+
+    if !import.combatants.is_empty() {
+        let mut characters = encounter::load_encounter_file();
+        let mut added = Vec::new();
+        for combatant in &import.combatants {
+            // Monsters whose statblock wasn't saved would have nothing to attack or roll with
+            let statblock = combatant.character_type.to_lowercase();
+            if skipped.contains(&statblock) && stat_search::find_statblock(&statblock).is_none() {
+                import.unsupported.push(format!("{}: wasn't added to the encounter because the {} statblock wasn't saved", combatant.name, combatant.character_type));
+                continue;
+            }
+            let mut combatant = combatant.clone();
+            combatant.name = encounter::unique_name(&combatant.name, &characters, false);
+            added.push(format!("Added {}/{} with initiative {}", combatant.character_type, combatant.name, combatant.initiative));
+            characters.push(combatant);
+        }
+        if !added.is_empty() {
+            encounter::save_added(&mut characters, &added);
+            done.extend(added);
+        }
+    }
+    done
+}
+
+///
+/// Prints what an import did, followed by everything that couldn't be brought over
+///
+pub fn print_report(done: &[String], unsupported: &[String]) {
+    if done.is_empty() {
+        println!("Nothing was imported");
+    }
+    for line in done {
+        println!("{}", line);
+    }
+    if !unsupported.is_empty() {
+        println!("\n{}", format!("Unsupported ({}):", unsupported.len()).yellow());
+        for line in unsupported {
+            println!("  {}", line);
+        }
+    }
+}
+
+///
+/// Asks for VTT export files and imports them
+///
+pub fn import_menu() {
+    println!("Enter the Foundry VTT or Roll20 JSON files to import, separated by commas, or press enter to return:");
+    let input = user_input::raw_input();
+    if input.is_empty() {
+        return;
+    }
+    let paths: Vec<String> = input.split(',').map(|path| path.trim().to_string()).filter(|path| !path.is_empty()).collect();
+    println!("\nAlso add actors that aren't in an exported combat to the encounter? (y/n)");
+    let add_actors = user_input::input() == "y";
+    println!();
+
+    match read_exports(&paths, add_actors) {
+        Ok(mut import) => {
+            let done = apply(&mut import);
+            print_report(&done, &import.unsupported);
+        },
+        Err(e) => println!("{}", e),
+    }
+    println!();
+}
@@ -0,0 +1,129 @@
+use crate::user_input;
+use crate::encounter;
+use crate::encounter::Character;
+use crate::log;
+use crate::log::EventKind;
+use crate::party;
+use crate::render;
+use crate::stat_search;
+use crate::turn;
+use colored::*;
+
+///
+/// Rerolls every monster's initiative from its statblock, returning what each one rolled
+///
+pub fn roll_monsters(characters: &mut [Character]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for character in characters.iter_mut().filter(|c| c.character_type != "Player") {
+        if let Some(creature) = stat_search::find_statblock(&character.character_type) {
+            character.initiative = stat_search::roll_initiative(&creature);
+        }
+        lines.push(format!("{}/{} rolled {}", character.character_type, character.name, character.initiative));
+    }
+    lines
+}
+
+///
+/// Rolls a player's initiative from their party roster bonus, or None if they aren't in the roster
+///
+pub fn roll_player(character: &Character) -> Option<i32> {
+    party::find_member(&character.name).map(|member| party::roll_initiative(&member))
+}
+
+///
+/// Parses one initiative per player from a line like "15, r, 8", where r rolls from the player's roster bonus.
+/// An empty line rolls for every player
+///
+pub fn parse_rolls(input: &str, players: &[&Character]) -> Result<Vec<i32>, String> {
+    let entries: Vec<&str> = if input.trim().is_empty() {
+        vec!["r"; players.len()]
+    } else {
+        input.split([',', ' ']).map(|entry| entry.trim()).filter(|entry| !entry.is_empty()).collect()
+    };
+    if entries.len() != players.len() {
+        return Err(format!("Enter {} initiatives, one for each player, but got {}", players.len(), entries.len()));
+    }
+
+    players.iter().zip(entries).map(|(player, entry)| match entry {
+        "r" => roll_player(player).ok_or(format!("{} isn't in the party roster, so enter their roll", player.name)),
+        _ => entry.parse().map_err(|_| format!("\"{}\" isn't a number or r", entry)),
+    }).collect()
+}
+
+///
+/// Records everyone's initiative, sorts the encounter once and starts the first round, returning a note for each
+/// creature skipped at the start
+///
+pub fn finish(characters: &mut [Character]) -> Vec<String> {
+    for character in characters.iter() {
+        log::record_for(EventKind::Roll, &format!("{}/{} rolled {} for initiative", character.character_type, character.name, character.initiative), None, &[character.id]);
+    }
+    encounter::save_encounter_file(characters);
+    log::record(EventKind::Turn, "Combat started", None);
+
+    // Surprised creatures and reinforcements at the top of the order don't get the first turn
+    let (state, notes) = turn::settle(&turn::TurnState::default(), characters);
+    for note in &notes {
+        log::record(EventKind::Turn, note, None);
+    }
+    if !notes.is_empty() {
+        encounter::write_encounter_file(characters);
+    }
+    turn::save_turn(&state);
+    notes
+}
+
+///
+/// Prints the players who need an initiative, in the order their rolls should be entered
+///
+fn print_players(players: &[&Character]) {
+    let lines: Vec<String> = players.iter().enumerate().map(|(number, player)| match party::find_member(&player.name) {
+        Some(member) => format!("{}. {} ({:+} from the roster)", number + 1, player.name, member.initiative_bonus),
+        None => format!("{}. {} (not in the roster)", number + 1, player.name),
+    }).collect();
+    let width = render::fit_width(&lines);
+    render::top(width);
+    render::centered(&"Player Initiative".bold().to_string(), width);
+    render::divider(width);
+    for line in &lines {
+        render::centered(&line.blue().to_string(), width);
+    }
+    render::bottom(width);
+}
+
+///
+/// Starts combat by rolling initiative for every monster and asking for all the player rolls at once
+///
+pub fn start_combat() {
+    let mut characters = encounter::load_encounter_file();
+    if characters.is_empty() {
+        println!("Add some creatures before starting combat!\n");
+        return;
+    }
+    let mut lines = roll_monsters(&mut characters);
+
+    let players: Vec<usize> = (0..characters.len()).filter(|&i| characters[i].character_type == "Player").collect();
+    if !players.is_empty() {
+        let player_refs: Vec<&Character> = players.iter().map(|&i| &characters[i]).collect();
+        print_players(&player_refs);
+        println!("\nEnter each player's rolled initiative in this order, separated by commas. Type r to roll from a player's roster bonus, or press enter to roll for everyone:");
+        let rolls = loop {
+            match parse_rolls(&user_input::input(), &player_refs) {
+                Ok(rolls) => break rolls,
+                Err(e) => println!("\n{}. Try again:", e),
+            }
+        };
+        for (&index, initiative) in players.iter().zip(rolls) {
+            characters[index].initiative = initiative;
+            lines.push(format!("Player/{} rolled {}", characters[index].name, initiative));
+        }
+    }
+
+    lines.extend(finish(&mut characters));
+    println!();
+    for line in &lines {
+        println!("{}", line);
+    }
+    println!("\nCombat has started! Press enter to continue:");
+    user_input::input();
+}
@@ -0,0 +1,211 @@
+use crate::user_input;
+use crate::render;
+use serde::{Serialize, Deserialize};
+use std::fs;
+use colored::*;
+use std::path::PathBuf;
+
+///
+/// Kinds of events recorded in the combat log
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Roll,
+    Hit,
+    Miss,
+    Damage,
+    Heal,
+    Save,
+    Condition,
+    Turn,
+    Add,
+    Remove,
+    Undo,
+    Redo,
+}
+
+///
+/// Event struct used for storing a single timestamped entry in the combat log
+///
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Event {
+    pub timestamp: String,
+    pub kind: EventKind,
+    pub message: String,
+    #[serde(default)]
+    pub amount: Option<i32>,
+    #[serde(default)]
+    pub creatures: Vec<u64>,
+}
+
+///
+/// Reads the current encounter's log, which is empty if it doesn't exist. Errors if the file can't be read or parsed,
+/// so it isn't saved over
+///
+pub fn read_log() -> Result<Vec<Event>, String> {
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker/log.json").into_owned();
+    let path = PathBuf::from(expanded_path);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("{} couldn't be read ({})", path.display(), e)),
+    };
+    serde_json::from_str(&content).map_err(|e| format!("{} couldn't be read as a combat log ({})", path.display(), e))
+}
+
+///
+/// Saves the log to ~/.config/dnd-encounter-tracker/log.json and creates the directory if it doesn't exist
+///
+fn save_log(events: &[Event]) {
+    let json_events = serde_json::to_string_pretty(events).unwrap();
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker").into_owned();
+    let path = PathBuf::from(expanded_path);
+    std::fs::create_dir_all(&path).expect("Failed to create directory");
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker/log.json").into_owned();
+    let path = PathBuf::from(expanded_path);
+    std::fs::write(&path, json_events).expect("Unable to write to file");
+}
+
+///
+/// Appends an event to the combat log, with an optional amount for damage and healing
+///
+pub fn record(kind: EventKind, message: &str, amount: Option<i32>) {
+    record_for(kind, message, amount, &[]);
+}
+
+///
+/// Appends an event about particular creatures to the combat log, keeping their IDs so the event still points at
+/// the right creatures when names repeat or change
+///
+pub fn record_for(kind: EventKind, message: &str, amount: Option<i32>, creatures: &[u64]) {
+    // An unreadable log is left alone rather than replaced by this one event
+    let mut events = match read_log() {
+        Ok(events) => events,
+        Err(e) => {
+            eprintln!("{}, so this wasn't logged: {}", e, message);
+            return;
+        }
+    };
+    events.push(Event {
+        timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        kind,
+        message: message.to_string(),
+        amount,
+        creatures: creatures.to_vec(),
+    });
+    save_log(&events);
+}
+
+///
+/// Empties the combat log, returning its events so they can be archived with the encounter. An unreadable log is
+/// left in place so it can be fixed by hand
+///
+pub fn clear_log() -> Vec<Event> {
+    match read_log() {
+        Ok(events) => {
+            save_log(&Vec::new());
+            events
+        },
+        Err(e) => {
+            eprintln!("{}, so it wasn't archived or cleared", e);
+            Vec::new()
+        }
+    }
+}
+
+///
+/// Renders the log as a Markdown table for session recaps
+///
+pub fn to_markdown(events: &[Event]) -> String {
+    let mut markdown = String::from("# Combat Log\n\n| Time | Event | Creatures | Details |\n| --- | --- | --- | --- |\n");
+    for event in events {
+        markdown.push_str(&format!("| {} | {:?} | {} | {} |\n", event.timestamp, event.kind, creature_ids(event), event.message.replace('|', "\\|")));
+    }
+    markdown
+}
+
+///
+/// Lists the IDs of the creatures an event involves, like "#2 #5"
+///
+fn creature_ids(event: &Event) -> String {
+    event.creatures.iter().map(|id| format!("#{}", id)).collect::<Vec<String>>().join(" ")
+}
+
+///
+/// Writes the log to a Markdown file, defaulting to ~/.config/dnd-encounter-tracker/log.md
+///
+fn export_markdown(events: &[Event]) {
+    println!("Enter a file to export to, or press enter for ~/.config/dnd-encounter-tracker/log.md:");
+    let mut input = user_input::raw_input();
+    if input.is_empty() {
+        input = "~/.config/dnd-encounter-tracker/log.md".to_string();
+    }
+    let path = PathBuf::from(shellexpand::tilde(&input).into_owned());
+    match std::fs::write(&path, to_markdown(events)) {
+        Ok(..) => println!("\nLog exported to {}\n", input),
+        Err(e) => println!("\nCouldn't export the log: {}\n", e),
+    }
+}
+
+///
+/// Displays the combat log, optionally only the events involving one creature, and allows it to be exported to Markdown
+///
+pub fn view_log() {
+    let all_events = match read_log() {
+        Ok(events) => events,
+        Err(e) => {
+            println!("{}. Fix or remove it to see the log\n", e);
+            return;
+        }
+    };
+    let mut filter: Option<u64> = None;
+
+    loop {
+        let events: Vec<Event> = all_events.iter().filter(|event| filter.is_none_or(|id| event.creatures.contains(&id))).cloned().collect();
+
+        // Escape code to clear screen
+        print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+        let width = render::width();
+        render::top(width);
+        let title = match filter {
+            Some(id) => format!("Combat Log for #{}:", id),
+            None => "Combat Log:".to_string(),
+        };
+        render::centered(&title.bold().to_string(), width);
+        render::bottom(width);
+        match filter {
+            Some(id) if events.is_empty() => println!("Nothing has happened to #{} yet!", id),
+            None if events.is_empty() => println!("Nothing has happened yet!"),
+            _ => {}
+        }
+        for event in &events {
+            let ids = creature_ids(event);
+            let line = if ids.is_empty() {
+                format!("[{}] {:<9} {}", event.timestamp, format!("{:?}", event.kind), event.message)
+            } else {
+                format!("[{}] {:<9} {} ({})", event.timestamp, format!("{:?}", event.kind), event.message, ids)
+            };
+            match event.kind {
+                EventKind::Damage | EventKind::Hit => println!("{}", line.red()),
+                EventKind::Heal => println!("{}", line.green()),
+                EventKind::Turn => println!("{}", line.bright_yellow()),
+                _ => println!("{}", line),
+            }
+        }
+
+        println!("\nType a creature ID like #4 to show only its events, (a)ll to show everything, (e) to export the log to Markdown, or press enter to return:");
+        let input = user_input::input();
+        if input == "e" {
+            println!();
+            export_markdown(&events);
+            return;
+        } else if input == "a" {
+            filter = None;
+        } else if let Some(id) = input.strip_prefix('#').and_then(|id| id.trim().parse().ok()) {
+            filter = Some(id);
+        } else {
+            return;
+        }
+    }
+}
@@ -1,6 +1,26 @@
+mod checks;
+mod cli;
+mod conditions;
+mod dice;
+mod difficulty;
 mod encounter;
+mod export;
+mod history;
+mod homebrew;
+mod import;
+mod initiative;
+mod log;
+mod party;
+mod render;
+mod rewards;
+mod rpc;
 mod stat_search;
+mod surprise;
+mod tui;
+mod turn;
 mod user_input;
+mod validation;
+mod web;
 
 use std::fs;
 use std::process::exit;
@@ -8,6 +28,17 @@ use std::path::Path;
 use serde::{Deserialize, Serialize};
 use colored::*;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+///
+/// Set while the player-facing view is shown, which hides monster stats from anyone looking at the screen
+///
+static PLAYER_VIEW: AtomicBool = AtomicBool::new(false);
+
+///
+/// Commands that are safe to run in the player view. The rest open menus that show the DM's full stats
+///
+const PLAYER_COMMANDS: [&str; 10] = ["c", "h", "j", "m", "n", "p", "u", "v", "w", "y"];
 
 ///
 /// Creature struct used for the main menu
@@ -18,7 +49,13 @@ struct Creature {
     character_type: String,
     ac: i32,
     hp: i32,
-    initiative: i32
+    #[serde(default)]
+    max_hp: i32,
+    initiative: i32,
+    #[serde(default)]
+    concentration: Option<String>,
+    #[serde(default)]
+    conditions: Vec<encounter::Condition>,
 }
  
 ///
@@ -42,56 +79,111 @@ fn load_encounter() -> Vec<Creature> {
 ///
 /// Main menu that displays current encounter and selected character
 ///
-fn print_creatures(position: usize, round: usize) {
-    let creatures = load_encounter();
+fn print_creatures() {
+    let turn::TurnState { round, position, .. } = turn::load_turn();
+    if PLAYER_VIEW.load(Ordering::SeqCst) {
+        print_player_view(position, round);
+        return;
+    }
+    let creatures = encounter::load_encounter_file();
+    let width = render::width();
     print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
-    println!("{}", format!("╔{:═<70}╗", "═"));
-    println!("{}", format!("║{:^70}║", format!("Current round: {round}").bold()));
-    println!("{}", format!("╟{:─<70}╢", "─"));
-    println!("{}", format!("║{:^70}║", format!("Creatures:").bold()));
-    println!("{}", format!("║{:70}║", " "));
-    
-    // Variable used for determining selected character
-    let mut selector = 1;
+    render::top(width);
+    render::centered(&format!("Current round: {round}").bold().to_string(), width);
+    render::divider(width);
+    render::centered(&"Creatures:".bold().to_string(), width);
+    render::centered("", width);
+
     // Used for determining whether or not to print actions (defaults to player)
     let mut creature_stat = "Player".to_string();
-    for creature in creatures {
+    for (index, creature) in creatures.iter().enumerate() {
+        // Players only show HP when it's tracked from the party roster, and the IDs tell apart creatures with the same name
+        let player_line = if encounter::tracks_player_hp(creature.max_hp) {
+            format!("{} - {} (#{}), AC: {}, HP: {}", creature.initiative, creature.name, creature.id, creature.ac, creature.hp)
+        } else {
+            format!("{} - {} (#{}), AC: {}", creature.initiative, creature.name, creature.id, creature.ac)
+        };
+        let monster_line = format!("{} - {}/{} (#{}), AC: {}, HP: {}", creature.initiative, creature.character_type, creature.name, creature.id, creature.ac, creature.hp);
+
         // True if it's the character's 'turn', false otherwise
-        if selector == position {
+        if index + 1 == position {
             if creature.character_type == "Player" {
-                println!("{}", format!("║{:^109}║", format!("{} {} {}", "-->".bright_yellow(), format!("{} - {}, AC: {}", creature.initiative, creature.name, creature.ac).bright_blue(), "<--".bright_yellow()).bold()));
+                render::centered(&format!("{} {} {}", "-->".bright_yellow(), player_line.bright_blue(), "<--".bright_yellow()).bold().to_string(), width);
             } else {
-                println!("{}", format!("║{:^109}║", format!("{} {} {}", "-->".bright_yellow(), format!("{} - {}/{}, AC: {}, HP: {}", creature.initiative, creature.character_type, creature.name, creature.ac, creature.hp).bright_red(), "<--".bright_yellow()).bold()));
+                render::centered(&format!("{} {} {}", "-->".bright_yellow(), monster_line.bright_red(), "<--".bright_yellow()).bold().to_string(), width);
 
                 // Changes variable to whatever the selected creature type is, allowing actions to be displayed below 
                 creature_stat = creature.character_type.clone();
             }
+        } else if creature.character_type == "Player" {
+            render::centered(&player_line.bright_blue().to_string(), width);
         } else {
-            if creature.character_type == "Player" {
-                println!("{}", format!("║{:^70}║", format!("{} - {}, AC: {}", creature.initiative, creature.name, creature.ac).bright_blue()));
-            } else {
-                println!("{}", format!("║{:^70}║", format!("{} - {}/{}, AC: {}, HP: {}", creature.initiative, creature.character_type, creature.name, creature.ac, creature.hp).bright_red()));
-            }
+            render::centered(&monster_line.bright_red().to_string(), width);
+        }
+
+        // Shows surprise, hiding, concentration and conditions below the creature if it has any
+        let mut effects = encounter::status_tags(creature, round);
+        if let Some(spell) = &creature.concentration {
+            effects.push(format!("Concentrating: {}", spell));
+        }
+        for condition in &creature.conditions {
+            effects.push(condition.name.clone());
+        }
+        if !effects.is_empty() {
+            render::centered(&format!("[{}]", effects.join(", ")).italic().to_string(), width);
         }
-        selector += 1;
     }
 
     // Prints actions if the selected character is not a player
     if creature_stat != "Player" {
-        println!("{}", format!("╟{:─<70}╢", "─"));
+        render::divider(width);
         stat_search::print_attributes(&creature_stat);
-        println!("{}", format!("╚{:═<70}╝", "═"));
+        render::bottom(width);
         stat_search::combat_stats(&creature_stat);
     } else {
-        println!("{}", format!("╚{:═<70}╝", "═"));
+        render::bottom(width);
     }
     println!();
 }
 
+///
+/// Player-facing version of the main menu, showing only names, turn order, conditions and how hurt monsters look
+///
+fn print_player_view(position: usize, round: usize) {
+    let characters = encounter::load_encounter_file();
+    let width = render::width();
+    print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+    render::top(width);
+    render::centered(&format!("Current round: {round}").bold().to_string(), width);
+    render::divider(width);
+    // Hidden creatures and reinforcements that haven't arrived are left out entirely
+    for (index, creature) in characters.iter().enumerate().filter(|(_, creature)| encounter::shown_to_players(creature, round)) {
+        let line = if creature.character_type == "Player" {
+            creature.name.bright_blue().to_string()
+        } else {
+            format!("{} ({})", creature.name, encounter::health_band(creature)).bright_red().to_string()
+        };
+        if index + 1 == position {
+            render::centered(&format!("{} {} {}", "-->".bright_yellow(), line, "<--".bright_yellow()).bold().to_string(), width);
+        } else {
+            render::centered(&line, width);
+        }
+        let mut effects: Vec<String> = creature.conditions.iter().map(|c| c.name.clone()).collect();
+        if let Some(spell) = &creature.concentration {
+            effects.insert(0, format!("Concentrating: {}", spell));
+        }
+        if !effects.is_empty() {
+            render::centered(&format!("[{}]", effects.join(", ")).italic().to_string(), width);
+        }
+    }
+    render::bottom(width);
+    println!("{}\n", "Player view: type m to return to the DM view".italic());
+}
+
 ///
 /// Only runs if encounter file does not exist or is empty. Forces initialization of encounter file
 ///
-fn initial_startup_loop(round: usize, position: usize) {
+fn initial_startup_loop() {
     // Control character printed to clear screen
     print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
     println!("Welcome to the D&D Combat Tracker!\n");
@@ -100,111 +192,213 @@ fn initial_startup_loop(round: usize, position: usize) {
     let creatures = load_encounter();
 
     // Restarts function if no characters were added
-    if creatures.len() == 0 {
-        initial_startup_loop(round, position);
+    if creatures.is_empty() {
+        initial_startup_loop();
     }
-    print_creatures(position, round);
+    print_creatures();
 }
 
 ///
 /// Main menu and loop for command input
 ///
 fn main() {
-    // Variable used for determining current round
-    let mut round = 1;
-    // Variable used for determining selected character
-    let mut position = 1;
+    // Runs a single non-interactive command and exits if one was given
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        exit(cli::run(&args));
+    }
+
     println!("Welcome to the D&D Combat Tracker!\n");
 
-    loop {
-        // Checks if encounter file exists, calls initialization function if it doesn't
-        let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker/encounter.json").into_owned();
-        let path = PathBuf::from(expanded_path);
-        let path = Path::new(&path);
-        if path.exists() {
-            print_creatures(position, round);
-        } else {
-            initial_startup_loop(round, position);
+    // Warns about statblocks that were skipped for being invalid
+    let problems = validation::check_library();
+    if !problems.is_empty() {
+        println!("{}", format!("Skipped invalid statblocks ({} problems):", problems.len()).yellow());
+        for problem in &problems {
+            println!("  {}", problem);
         }
+        println!("\nPress enter to continue:");
+        user_input::input();
+    }
 
-        // Command loop, allowing user to type commands
-        loop {
-            let creatures = load_encounter();
-            if creatures.len() == 0 {
-                round = 1;
-                position = 1;
-                initial_startup_loop(round, position);
-            }
+    // Checks if encounter file exists, calls initialization function if it doesn't.
+    // The turn is read back from the saved turn state, so restarts pick up where the encounter left off
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker/encounter.json").into_owned();
+    let path = PathBuf::from(expanded_path);
+    if Path::new(&path).exists() {
+        print_creatures();
+    } else {
+        initial_startup_loop();
+    }
 
-            // Goes to next round if all characters have taken a turn
-            if position == creatures.len()+1 {round+=1; position=0; break;}
+    // Command loop, allowing user to type commands
+    loop {
+        let creatures = load_encounter();
+        if creatures.is_empty() {
+            initial_startup_loop();
+        }
 
-            println!("Enter a command! Type h for help menu: ");
-            let input: String = user_input::input();
-            println!();
-            if input == "quit" || input == "done" {
-                println!("I hope you enjoyed using this program!");
-                exit(0);
-            }
+        println!("Enter a command! Type h for help menu: ");
+        let input: String = user_input::input();
+        println!();
+        if input == "quit" || input == "done" {
+            println!("I hope you enjoyed using this program!");
+            exit(0);
+        }
+        if PLAYER_VIEW.load(Ordering::SeqCst) && !PLAYER_COMMANDS.contains(&input.as_str()) {
+            println!("That isn't available in the player view. Type m to return to the DM view first\n");
+            continue;
+        }
 
-            match input.as_str() {
-                "n" => {break;},
-                
-                // Basically just makes sure there aren't any underflow errors
-                "p" => {
-                    if position == 1 {
-                        position = creatures.len()-1;
-                        if round == 1 {
-                            round = 1
-                        } else {
-                            round -= 1;
-                        }
-                    } else {
-                        position -=2;
+        match input.as_str() {
+            "n" | "p" => {
+                // Skips anyone who can't act, showing the DM who joined or lost their turn along the way
+                if let Some((_, notes)) = turn::take_turn(input == "n") {
+                    print_creatures();
+                    if PLAYER_VIEW.load(Ordering::SeqCst) {
+                        continue;
+                    }
+                    for note in &notes[..notes.len()-1] {
+                        println!("{}", note.italic());
+                    }
+                    if notes.len() > 1 {
+                        println!();
                     }
-                    break;
-                },
-                "s" => {stat_search::statblocks();},
-                "a" => {
-                    encounter::add_character();
-                    print_creatures(position, round);
-                },
-                "e" => {
-                    encounter::edit_creature();
-                    print_creatures(position, round);
-                },
-                "r" => {
-                    encounter::remove_creature();
-                    print_creatures(position, round);
-                },
-                "c" => {
-                    print_creatures(position, round);
-                },
-                "d" => {
-                    encounter::damage_creature();
-                    print_creatures(position, round);
-                },
-                "t" => {
-                    encounter::attack();
-                    print_creatures(position, round);
-                },
-                "h" => {println!("Commands:
+                }
+            },
+            "s" => {stat_search::statblocks();},
+            "b" => {
+                homebrew::homebrew_menu();
+                print_creatures();
+            },
+            "a" => {
+                encounter::add_character();
+                print_creatures();
+            },
+            "f" => {
+                initiative::start_combat();
+                print_creatures();
+            },
+            "e" => {
+                encounter::edit_creature();
+                print_creatures();
+            },
+            "r" => {
+                encounter::remove_creature();
+                print_creatures();
+            },
+            "c" => {
+                print_creatures();
+            },
+            "d" => {
+                encounter::damage_creature();
+                print_creatures();
+            },
+            "j" => {
+                // The full-screen interface keeps the player view setting, so it can be opened in either view
+                if let Err(e) = tui::run() {
+                    println!("Couldn't open the full-screen interface: {}\n", e);
+                    continue;
+                }
+                print_creatures();
+            },
+            "k" => {
+                checks::checks();
+                print_creatures();
+            },
+            "l" => {
+                log::view_log();
+                print_creatures();
+            },
+            "o" => {
+                conditions::manage_conditions();
+                print_creatures();
+            },
+            "t" => {
+                encounter::attack();
+                print_creatures();
+            },
+            "u" | "y" => {
+                // Restores the turn along with the encounter, since turn advances can be undone too
+                match if input == "u" {history::undo()} else {history::redo()} {
+                    Ok(..) => {
+                        print_creatures();
+                        continue;
+                    },
+                    Err(e) => println!("{}!\n", e),
+                }
+            },
+            "g" => {
+                party::party_menu();
+                print_creatures();
+            },
+            "i" => {
+                import::import_menu();
+                print_creatures();
+            },
+            "z" => {
+                surprise::surprise_menu();
+                print_creatures();
+            },
+            "v" => {
+                web::start_dashboard();
+            },
+            "w" => {
+                export::export_encounter(PLAYER_VIEW.load(Ordering::SeqCst));
+            },
+            "m" => {
+                // Switches between the DM and player views
+                PLAYER_VIEW.fetch_xor(true, Ordering::SeqCst);
+                print_creatures();
+            },
+            "x" => {
+                // Starts a fresh encounter once the current one has been ended and archived
+                if rewards::end_encounter(turn::load_turn().round) {
+                    initial_startup_loop();
+                } else {
+                    print_creatures();
+                }
+            },
+            "h" if PLAYER_VIEW.load(Ordering::SeqCst) => {println!("Player view commands:
+c: clear screen
+j: open the full-screen interface
+m: return to the DM view
+n: next character
+p: previous character
+u: undo last change
+v: start the player dashboard for a second screen
+w: export the player-safe encounter to Markdown, HTML or text
+y: redo last undone change
+");},
+            "h" => {println!("Commands:
 a: add creature
+b: homebrew creatures
 c: clear screen
 d: damage creature
 e: edit stats
+f: start combat, rolling initiative for everyone
+g: party roster
+i: import from Foundry VTT or Roll20
+j: open the full-screen interface
+k: ability checks and contests
+l: combat log
+m: switch between DM and player views
 n: next character
+o: conditions and concentration
 p: previous character
 r: remove character
 s: stat search
 t: attack action
+u: undo last change
+v: start the player dashboard for a second screen
+w: export encounter to Markdown, HTML or text (player-safe in the player view)
+x: end encounter
+y: redo last undone change
+z: surprised, hidden and reinforcement creatures
 ");},
-                _ => {
-                    println!("Invalid command!");
-                }
+            _ => {
+                println!("Invalid command!");
             }
         }
-        // Moves to next character after a loop break
-        position += 1;
     }
 }
@@ -0,0 +1,283 @@
+use crate::user_input;
+use crate::user_input::Picked;
+use crate::encounter::Character;
+use crate::render;
+use serde::{Serialize, Deserialize};
+use titlecase::titlecase;
+use colored::*;
+use rand::Rng;
+use std::fs;
+use std::path::PathBuf;
+
+///
+/// PartyMember struct used for storing a player character between encounters
+///
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PartyMember {
+    pub name: String,
+    pub ac: i32,
+    pub max_hp: i32,
+    pub initiative_bonus: i32,
+    pub passive_perception: i32,
+    pub level: i32,
+    #[serde(default)]
+    pub class: String,
+}
+
+///
+/// Path of the party roster, ~/.config/dnd-encounter-tracker/party.json
+///
+fn party_path() -> PathBuf {
+    PathBuf::from(shellexpand::tilde("~/.config/dnd-encounter-tracker/party.json").into_owned())
+}
+
+///
+/// Reads the party roster, or an empty party if it doesn't exist yet. Returns an error if the file can't be read as
+/// a roster, so it isn't mistaken for an empty one and saved over
+///
+pub fn read_party() -> Result<Vec<PartyMember>, String> {
+    let path = party_path();
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("{} couldn't be read ({})", path.display(), e)),
+    };
+    serde_json::from_str(&content).map_err(|e| format!("{} couldn't be read as a party roster ({})", path.display(), e))
+}
+
+///
+/// Loads the party roster, warning and using an empty party if it can't be read
+///
+pub fn load_party() -> Vec<PartyMember> {
+    read_party().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        Vec::new()
+    })
+}
+
+///
+/// Saves the party roster and creates the directory if it doesn't exist
+///
+fn save_party(party: &[PartyMember]) {
+    let json_party = serde_json::to_string_pretty(party).unwrap();
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker").into_owned();
+    std::fs::create_dir_all(PathBuf::from(expanded_path)).expect("Failed to create directory");
+    std::fs::write(party_path(), json_party).expect("Unable to write to file");
+}
+
+///
+/// Finds a party member by name
+///
+pub fn find_member(name: &str) -> Option<PartyMember> {
+    load_party().into_iter().find(|member| member.name.to_lowercase() == name.trim().to_lowercase())
+}
+
+///
+/// Rolls a party member's initiative
+///
+pub fn roll_initiative(member: &PartyMember) -> i32 {
+    rand::thread_rng().gen_range(1..21) + member.initiative_bonus
+}
+
+///
+/// Turns a party member into a player in the encounter, with their real HP instead of the placeholder
+///
+pub fn to_character(member: &PartyMember, initiative: i32) -> Character {
+    Character {
+        id: 0,
+        name: member.name.clone(),
+        character_type: "Player".to_string(),
+        ac: member.ac,
+        hp: member.max_hp,
+        max_hp: member.max_hp,
+        initiative,
+        level: member.level,
+        concentration: None,
+        conditions: Vec::new(),
+        surprised: false,
+        hidden: false,
+        joins_round: None,
+    }
+}
+
+///
+/// Adds everyone in the party who isn't already in the encounter, rolling their initiative or asking for each roll.
+/// Returns a log message for each player added, to be recorded once they're saved with their IDs
+///
+pub fn add_party(characters: &mut Vec<Character>) -> Vec<String> {
+    let party = match read_party() {
+        Ok(party) => party,
+        Err(e) => {
+            println!("{}\n", e);
+            return Vec::new();
+        }
+    };
+    if party.is_empty() {
+        println!("The party roster is empty! Add players to it with g from the main menu\n");
+        return Vec::new();
+    }
+    println!("(r)oll initiative for the party, or (e)nter each player's roll?");
+    let roll = user_input::input() != "e";
+    println!();
+
+    let mut added = Vec::new();
+    for member in &party {
+        if characters.iter().any(|c| c.character_type == "Player" && c.name.to_lowercase() == member.name.to_lowercase()) {
+            println!("{} is already in the encounter", member.name);
+            continue;
+        }
+        let initiative = if roll {
+            roll_initiative(member)
+        } else {
+            println!("Enter {}'s rolled initiative ({:+}):", member.name, member.initiative_bonus);
+            user_input::int_input()
+        };
+        println!("Added {} with initiative {}", member.name, initiative);
+        added.push(format!("Added Player/{} with initiative {}", member.name, initiative));
+        characters.push(to_character(member, initiative));
+    }
+    println!();
+    added
+}
+
+///
+/// Prints the roster, numbering each member
+///
+fn print_party(party: &[PartyMember]) {
+    let lines: Vec<String> = party.iter().enumerate().map(|(number, member)| {
+        let class = if member.class.is_empty() {String::new()} else {format!(" {}", member.class)};
+        format!("{}. {}, level {}{} - AC {}, {} HP, initiative {:+}, passive Perception {}", number + 1, member.name, member.level, class, member.ac, member.max_hp, member.initiative_bonus, member.passive_perception)
+    }).collect();
+    let width = render::fit_width(&lines);
+    render::top(width);
+    render::centered(&"Party Roster:".bold().to_string(), width);
+    render::divider(width);
+    if lines.is_empty() {
+        render::centered("Nobody yet", width);
+    }
+    for (index, line) in lines.iter().enumerate() {
+        if index != 0 {
+            render::dotted(width);
+        }
+        render::centered(&line.blue().to_string(), width);
+    }
+    render::bottom(width);
+}
+
+///
+/// Asks for a whole number, keeping the current value if nothing is entered
+///
+fn number_input(prompt: &str, current: Option<i32>) -> i32 {
+    match current {
+        Some(current) => println!("{} (press enter to keep {}):", prompt, current),
+        None => println!("{}:", prompt),
+    }
+    loop {
+        let input = user_input::input();
+        match (input.parse::<i32>(), current) {
+            (Ok(number), _) => return number,
+            (Err(..), Some(current)) if input.is_empty() => return current,
+            _ => println!("\nPlease enter a valid integer:"),
+        }
+    }
+}
+
+///
+/// Asks for each of a party member's stats, starting from an existing member when editing
+///
+fn member_input(current: Option<&PartyMember>) -> PartyMember {
+    let name = match current {
+        Some(member) => {
+            println!("Enter the player's name (press enter to keep {}):", member.name);
+            let name = titlecase(&user_input::input());
+            if name.is_empty() {member.name.clone()} else {name}
+        },
+        None => {
+            println!("Enter the player's name:");
+            loop {
+                let name = titlecase(&user_input::input());
+                if !name.is_empty() {
+                    break name;
+                }
+                println!("\nThe name can't be empty:");
+            }
+        }
+    };
+    println!("\nEnter {}'s class (press enter to {}):", name, if current.is_some() {"keep it"} else {"leave it blank"});
+    let class = match titlecase(&user_input::input()) {
+        class if class.is_empty() => current.map(|member| member.class.clone()).unwrap_or_default(),
+        class => class,
+    };
+    println!();
+    let level = number_input(&format!("Enter {}'s level", name), current.map(|member| member.level));
+    println!();
+    let ac = number_input(&format!("Enter {}'s AC", name), current.map(|member| member.ac));
+    println!();
+    let max_hp = number_input(&format!("Enter {}'s max HP", name), current.map(|member| member.max_hp));
+    println!();
+    let initiative_bonus = number_input(&format!("Enter {}'s initiative bonus", name), current.map(|member| member.initiative_bonus));
+    println!();
+    let passive_perception = number_input(&format!("Enter {}'s passive Perception", name), current.map(|member| member.passive_perception));
+    PartyMember { name, ac, max_hp, initiative_bonus, passive_perception, level, class }
+}
+
+///
+/// Menu used to add, edit and remove the players in the party roster
+///
+pub fn party_menu() {
+    loop {
+        print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+        // Editing an unreadable roster would save over it, so it has to be fixed or removed first
+        let mut party = match read_party() {
+            Ok(party) => party,
+            Err(e) => {
+                println!("{}\n\nFix or remove the file to edit the party roster. Press enter to return:", e);
+                user_input::input();
+                return;
+            }
+        };
+        print_party(&party);
+
+        println!("\n(a)dd a player, (e)dit a player, (r)emove a player, or type \"done\" to return:");
+        let input = user_input::input();
+        println!();
+        match input.as_str() {
+            "a" => {
+                let member = member_input(None);
+                if party.iter().any(|existing| existing.name.to_lowercase() == member.name.to_lowercase()) {
+                    println!("\n{} is already in the party!", member.name);
+                    println!("\nPress enter to continue:");
+                    user_input::input();
+                    continue;
+                }
+                party.push(member);
+            },
+            "e" | "r" => {
+                let pickable = user_input::can_pick(party.len());
+                println!("{} the player to {}:", if pickable {"Choose"} else {"Enter the number of"}, if input == "e" {"edit"} else {"remove"});
+                let number = if pickable {
+                    let names: Vec<String> = party.iter().map(|member| member.name.clone()).collect();
+                    match user_input::pick(&names) {
+                        Picked::Chosen(chosen) => chosen.first().map(|index| index + 1).unwrap_or(0),
+                        Picked::Typed(typed) => typed.parse().unwrap_or(0),
+                        Picked::Cancelled => 0,
+                    }
+                } else {
+                    user_input::usize_input()
+                };
+                println!();
+                if number == 0 || number > party.len() {
+                    continue;
+                }
+                if input == "e" {
+                    party[number - 1] = member_input(Some(&party[number - 1]));
+                } else {
+                    party.remove(number - 1);
+                }
+            },
+            "done" | "" => break,
+            _ => continue,
+        }
+        save_party(&party);
+    }
+}
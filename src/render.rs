@@ -0,0 +1,303 @@
+use ratatui::crossterm::terminal;
+use unicode_width::UnicodeWidthChar;
+
+///
+/// Widest a box will grow on large terminals, so lines stay readable
+///
+const MAX_WIDTH: usize = 100;
+
+///
+/// Narrowest a box will shrink to, even on tiny terminals
+///
+const MIN_WIDTH: usize = 30;
+
+///
+/// Width of the inside of a box (not counting borders) that fits the terminal, falling back to 70 columns when it can't be measured
+///
+pub fn width() -> usize {
+    match terminal::size() {
+        Ok((columns, _)) => (columns as usize).saturating_sub(2).clamp(MIN_WIDTH, MAX_WIDTH),
+        Err(..) => 70,
+    }
+}
+
+///
+/// Width of a box just wide enough for its contents, starting at 35 columns and never wider than the terminal
+///
+pub fn fit_width(lines: &[String]) -> usize {
+    let widest = lines.iter().map(|line| visible_width(line) + 2).max().unwrap_or(0);
+    widest.clamp(35, std::cmp::max(width(), 35))
+}
+
+///
+/// Measures how many columns a string takes up on screen, ignoring ANSI color codes
+///
+pub fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skips the escape sequence up to and including its final letter
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            width += c.width().unwrap_or(0);
+        }
+    }
+    width
+}
+
+///
+/// Finds the byte index where a string passes a visible width, so it can be split there without cutting through an
+/// ANSI color code. Always keeps at least one visible character before the split
+///
+fn split_index(text: &str, width: usize) -> usize {
+    let mut columns = 0;
+    let mut chars = text.char_indices();
+    while let Some((index, c)) = chars.next() {
+        if c == '\x1b' {
+            for (_, c) in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        let char_width = c.width().unwrap_or(0);
+        if columns > 0 && columns + char_width > width {
+            return index;
+        }
+        columns += char_width;
+    }
+    text.len()
+}
+
+///
+/// Makes each wrapped line stand on its own: colors still on at the end of a line are reset there and turned back on
+/// at the start of the next, so a colored line doesn't bleed into the box border or lose its color when it's wrapped
+///
+fn close_colors(lines: Vec<String>) -> Vec<String> {
+    let mut active = String::new();
+    lines.into_iter().map(|line| {
+        let mut closed = active.clone();
+        closed.push_str(&line);
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            if c != '\x1b' {
+                continue;
+            }
+            let mut code = String::from(c);
+            for c in chars.by_ref() {
+                code.push(c);
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            if code == "\x1b[0m" || code == "\x1b[m" {
+                active.clear();
+            } else {
+                active.push_str(&code);
+            }
+        }
+        if !active.is_empty() {
+            closed.push_str("\x1b[0m");
+        }
+        closed
+    }).collect()
+}
+
+///
+/// Word-wraps text to a visible width, breaking words that are longer than a whole line
+///
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = std::cmp::max(width, 1);
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split(' ') {
+        let mut word = word.to_string();
+        while visible_width(&word) > width {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            let split = split_index(&word, width);
+            lines.push(word[..split].to_string());
+            word = word[split..].to_string();
+        }
+        if current.is_empty() {
+            current = word;
+        } else if visible_width(&current) + 1 + visible_width(&word) <= width {
+            current.push(' ');
+            current.push_str(&word);
+        } else {
+            lines.push(std::mem::replace(&mut current, word));
+        }
+    }
+    lines.push(current);
+    close_colors(lines)
+}
+
+///
+/// Prints the top border of a box
+///
+pub fn top(width: usize) {
+    println!("╔{}╗", "═".repeat(width));
+}
+
+///
+/// Prints the bottom border of a box
+///
+pub fn bottom(width: usize) {
+    println!("╚{}╝", "═".repeat(width));
+}
+
+///
+/// Prints a solid divider between sections of a box
+///
+pub fn divider(width: usize) {
+    println!("╟{}╢", "─".repeat(width));
+}
+
+///
+/// Prints a dotted divider between entries in a box
+///
+pub fn dotted(width: usize) {
+    println!("╟{}╢", "┄".repeat(width));
+}
+
+///
+/// Prints the bottom of a heading box that opens onto unboxed text below it
+///
+pub fn open_bottom(width: usize) {
+    println!("╙{}╜", "─".repeat(width));
+}
+
+///
+/// Prints a horizontal rule the full width of a box, borders included
+///
+pub fn rule(width: usize, fill: &str) {
+    println!("{}", fill.repeat(width + 2));
+}
+
+///
+/// Prints text centered inside a box, wrapping it over several lines if it's too long
+///
+pub fn centered(text: &str, width: usize) {
+    for line in wrap(text, width) {
+        let padding = width.saturating_sub(visible_width(&line));
+        println!("║{}{}{}║", " ".repeat(padding / 2), line, " ".repeat(padding - padding / 2));
+    }
+}
+
+///
+/// Prints cells side by side inside a box, splitting the width evenly between them
+///
+pub fn columns(cells: &[String], width: usize) {
+    let separators = cells.len().saturating_sub(1);
+    let cell_width = width.saturating_sub(separators) / std::cmp::max(cells.len(), 1);
+
+    // Cells too wide for the box are stacked instead
+    if cells.iter().any(|cell| visible_width(cell) > cell_width) {
+        for cell in cells {
+            centered(cell, width);
+        }
+        return;
+    }
+
+    let mut row = String::new();
+    for (index, cell) in cells.iter().enumerate() {
+        // The last cell takes whatever's left over from the division
+        let this_width = if index == cells.len() - 1 {width - (cell_width + 1) * separators} else {cell_width};
+        let padding = this_width - visible_width(cell);
+        row.push_str(&format!("{}{}{}", " ".repeat(padding / 2), cell, " ".repeat(padding - padding / 2)));
+        if index != cells.len() - 1 {
+            row.push('│');
+        }
+    }
+    println!("║{}║", row);
+}
+
+///
+/// Prints unboxed text wrapped to the width of a box, indenting continuation lines
+///
+pub fn paragraph(text: &str, width: usize) {
+    let lines = wrap(text, width.saturating_sub(2));
+    for (index, line) in lines.iter().enumerate() {
+        if index == 0 {
+            println!("{}", line);
+        } else {
+            println!("  {}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_width_skips_color_codes() {
+        assert_eq!(visible_width("\x1b[31mred\x1b[0m"), 3);
+        assert_eq!(visible_width("\x1b[1;33mbold\x1b[0m and plain"), 14);
+    }
+
+    #[test]
+    fn visible_width_counts_wide_characters_twice() {
+        assert_eq!(visible_width("日本語"), 6);
+        assert_eq!(visible_width("\x1b[1m日本\x1b[0m!"), 5);
+    }
+
+    #[test]
+    fn split_index_keeps_color_codes_whole() {
+        let text = "\x1b[31mabcdef";
+        let split = split_index(text, 3);
+        assert_eq!(&text[..split], "\x1b[31mabc");
+    }
+
+    #[test]
+    fn split_index_doesnt_split_wide_characters() {
+        assert_eq!(split_index("日本語", 3), "日".len());
+        assert_eq!(split_index("日本語", 4), "日本".len());
+    }
+
+    #[test]
+    fn split_index_keeps_one_character_when_it_is_too_wide() {
+        assert_eq!(split_index("日本", 1), "日".len());
+    }
+
+    #[test]
+    fn split_index_returns_the_end_when_it_fits() {
+        assert_eq!(split_index("abc", 10), 3);
+    }
+
+    #[test]
+    fn wrap_breaks_between_words() {
+        assert_eq!(wrap("the quick brown fox", 10), vec!["the quick", "brown fox"]);
+        assert_eq!(wrap("", 10), vec![""]);
+    }
+
+    #[test]
+    fn wrap_breaks_long_words() {
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn wrap_fits_wide_characters() {
+        let lines = wrap("日本語テキスト", 5);
+        assert_eq!(lines, vec!["日本", "語テ", "キス", "ト"]);
+        assert!(lines.iter().all(|line| visible_width(line) <= 5));
+    }
+
+    #[test]
+    fn wrap_measures_without_color_codes() {
+        assert_eq!(wrap("\x1b[32mgreen\x1b[0m grass", 11), vec!["\x1b[32mgreen\x1b[0m grass"]);
+    }
+
+    #[test]
+    fn wrap_carries_colors_across_lines() {
+        let lines = wrap("\x1b[31mred words here\x1b[0m", 9);
+        assert_eq!(lines, vec!["\x1b[31mred words\x1b[0m", "\x1b[31mhere\x1b[0m"]);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::user_input;
+use crate::encounter;
+use crate::encounter::Character;
+use crate::stat_search;
+use crate::dice;
+use crate::log;
+use crate::history;
+use crate::render;
+use crate::turn;
+use serde::Serialize;
+use colored::*;
+use rand::Rng;
+use std::path::PathBuf;
+
+///
+/// ArchivedEncounter struct used for saving a finished encounter to the archive folder
+///
+#[derive(Serialize, Debug)]
+struct ArchivedEncounter {
+    ended: String,
+    rounds: usize,
+    total_xp: i32,
+    xp_per_player: i32,
+    loot: Vec<String>,
+    characters: Vec<Character>,
+    log: Vec<log::Event>,
+}
+
+///
+/// Rolls each defeated monster's loot table, returning a line for every item that dropped
+///
+fn roll_loot(defeated: &[&Character]) -> Vec<String> {
+    let mut loot = Vec::new();
+    for monster in defeated {
+        for item in stat_search::monster_loot(&monster.character_type) {
+            if rand::thread_rng().gen_range(1..101) > item.chance {
+                continue;
+            }
+            let quantity = match dice::roll(&item.quantity) {
+                Some((total, _)) => total,
+                None => 1,
+            };
+            if quantity > 0 {
+                loot.push(format!("{}/{} dropped {} {}", monster.character_type, monster.name, quantity, item.name));
+            }
+        }
+    }
+    loot
+}
+
+///
+/// Saves a finished encounter to ~/.config/dnd-encounter-tracker/archive, named after the time it ended
+///
+fn archive_encounter(archived: &ArchivedEncounter) {
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker/archive").into_owned();
+    let path = PathBuf::from(expanded_path);
+    std::fs::create_dir_all(&path).expect("Failed to create directory");
+    let file_name = format!("encounter-{}.json", chrono::Local::now().format("%Y-%m-%d_%H-%M-%S"));
+    let json_encounter = serde_json::to_string_pretty(archived).unwrap();
+    std::fs::write(path.join(&file_name), json_encounter).expect("Unable to write to file");
+    println!("Encounter archived to ~/.config/dnd-encounter-tracker/archive/{}", file_name);
+}
+
+///
+/// Ends the encounter, splitting XP from defeated monsters between the players, rolling loot and archiving the encounter
+///
+pub fn end_encounter(round: usize) -> bool {
+    let characters = encounter::load_encounter_file();
+    println!("Are you sure you want to end the encounter? (y/n)");
+    if user_input::input() != "y" {
+        return false;
+    }
+
+    let players = characters.iter().filter(|c| c.character_type == "Player").count();
+    let defeated: Vec<&Character> = characters.iter().filter(|c| c.character_type != "Player" && c.hp <= 0).collect();
+    let total_xp: i32 = defeated.iter().map(|m| stat_search::monster_xp(&m.character_type).unwrap_or(0)).sum();
+    let xp_per_player = if players > 0 {total_xp / players as i32} else {0};
+    let loot = roll_loot(&defeated);
+
+    let width = render::width();
+    println!();
+    render::top(width);
+    render::centered(&format!("Encounter over after {} rounds!", round).bold().to_string(), width);
+    render::divider(width);
+    render::centered(&format!("Defeated {} monsters worth {} XP", defeated.len(), total_xp), width);
+    render::centered(&format!("{} XP for each of {} players", xp_per_player, players), width);
+    render::divider(width);
+    if loot.is_empty() {
+        render::centered("No loot dropped", width);
+    }
+    for item in &loot {
+        render::centered(item, width);
+    }
+    render::bottom(width);
+    println!();
+
+    archive_encounter(&ArchivedEncounter {
+        ended: chrono::Local::now().to_rfc3339(),
+        rounds: round,
+        total_xp,
+        xp_per_player,
+        loot,
+        characters,
+        log: log::clear_log(),
+    });
+
+    // Clears the encounter so the next one starts fresh
+    encounter::write_encounter_file(&[]);
+    turn::write_turn(&turn::TurnState::default());
+    history::clear_history();
+    println!("\nPress enter to continue:");
+    user_input::input();
+    true
+}
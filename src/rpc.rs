@@ -0,0 +1,525 @@
+use crate::encounter;
+use crate::encounter::{Character, Condition};
+use crate::stat_search;
+use crate::conditions;
+use crate::history;
+use crate::log;
+use crate::log::EventKind;
+use crate::turn;
+use crate::surprise;
+use serde_json::{json, Value};
+use titlecase::titlecase;
+use std::fs;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::UnixListener;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+///
+/// Standard JSON-RPC 2.0 error codes
+///
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+///
+/// How often the encounter files are checked for changes made outside of the connection
+///
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+///
+/// Held while a request runs, since every connection changes the same encounter files
+///
+static REQUEST_LOCK: Mutex<()> = Mutex::new(());
+
+///
+/// Output shared by a connection's responses and its change events
+///
+type Output = Arc<Mutex<Box<dyn Write + Send>>>;
+
+///
+/// RpcError struct used for reporting a failed request back to the client
+///
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+///
+/// Builds an error for a request with missing or invalid parameters
+///
+fn invalid(message: String) -> RpcError {
+    RpcError { code: INVALID_PARAMS, message }
+}
+
+///
+/// Gets a required string parameter
+///
+fn str_param<'a>(params: &'a Value, key: &str) -> Result<&'a str, RpcError> {
+    params.get(key).and_then(|value| value.as_str()).ok_or_else(|| invalid(format!("\"{}\" must be a string", key)))
+}
+
+///
+/// Gets an optional whole number parameter
+///
+fn optional_int(params: &Value, key: &str) -> Result<Option<i32>, RpcError> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value.as_i64().and_then(|number| i32::try_from(number).ok()).map(Some)
+            .ok_or_else(|| invalid(format!("\"{}\" must be a whole number between {} and {}", key, i32::MIN, i32::MAX))),
+    }
+}
+
+///
+/// Gets a required whole number parameter
+///
+fn int_param(params: &Value, key: &str) -> Result<i32, RpcError> {
+    optional_int(params, key)?.ok_or_else(|| invalid(format!("\"{}\" must be a whole number", key)))
+}
+
+///
+/// Finds the creature a parameter refers to, by its number in turn order, an ID like "#4", or by name, prefix or type/name
+///
+fn creature_param(params: &Value, key: &str, characters: &[Character]) -> Result<usize, RpcError> {
+    let token = match params.get(key) {
+        Some(Value::Number(number)) => number.to_string(),
+        Some(Value::String(name)) => name.clone(),
+        _ => return Err(invalid(format!("\"{}\" must be a creature number, ID or name", key))),
+    };
+    let matches = encounter::find_creatures(&token, characters);
+    match matches.len() {
+        0 => Err(invalid(format!("No creature matches \"{}\"", token))),
+        1 => Ok(matches[0]),
+        _ => {
+            let names: Vec<String> = matches.iter().map(|&i| format!("{} (#{})", conditions::caster_label(&characters[i]), characters[i].id)).collect();
+            Err(invalid(format!("\"{}\" matches more than one creature: {}. Use an ID like \"#{}\" to pick one", token, names.join(", "), characters[matches[0]].id)))
+        }
+    }
+}
+
+///
+/// Nobody can be prompted over the API, so any save that can't be rolled from a statblock, like a player's, has to
+/// come with the request
+///
+fn require_player_roll(character: &Character, ability: &str, roll: Option<i32>, key: &str, reason: &str) -> Result<(), RpcError> {
+    if stat_search::save_bonus(&character.character_type, ability).is_none() && roll.is_none() {
+        return Err(invalid(format!("{} {} that can't be rolled from a statblock, so \"{}\" must give the roll", conditions::caster_label(character), reason, key)));
+    }
+    Ok(())
+}
+
+///
+/// The current round, turn position and creatures, sent as the result of encounter.get and with every change event
+///
+fn encounter_state() -> Value {
+    let state = turn::load_turn();
+    json!({
+        "round": state.round,
+        "position": state.position,
+        "creatures": encounter::load_encounter_file(),
+    })
+}
+
+///
+/// Adds monsters from the statblock library: {monster, name?, count?}
+///
+fn add(params: &Value) -> Result<Value, RpcError> {
+    let monster_type = str_param(params, "monster")?;
+    let count = optional_int(params, "count")?.unwrap_or(1);
+    if !(1..=encounter::MAX_COUNT).contains(&count) {
+        return Err(invalid(format!("\"count\" must be between 1 and {}", encounter::MAX_COUNT)));
+    }
+
+    let mut characters = encounter::load_encounter_file();
+    let base = titlecase(params.get("name").and_then(|name| name.as_str()).unwrap_or(monster_type));
+    let mut added = Vec::new();
+    for _ in 0..count {
+        let name = encounter::unique_name(&base, &characters, count > 1);
+        let character = stat_search::create_monster(monster_type, name).ok_or_else(|| {
+            let suggestions = stat_search::suggest_monsters(monster_type);
+            invalid(if suggestions.is_empty() {
+                format!("Unknown monster: {}", monster_type)
+            } else {
+                format!("Unknown monster: {}. Did you mean {}?", monster_type, suggestions.join(", "))
+            })
+        })?;
+        added.push(format!("Added {}/{} with initiative {}", character.character_type, character.name, character.initiative));
+        characters.push(character);
+    }
+    let ids = encounter::save_added(&mut characters, &added);
+    let added: Vec<&Character> = ids.iter().filter_map(|id| characters.iter().find(|character| character.id == *id)).collect();
+    Ok(json!(added))
+}
+
+///
+/// Adds a player: {name, ac, initiative, level?}
+///
+fn add_player(params: &Value) -> Result<Value, RpcError> {
+    let character = Character {
+        id: 0,
+        name: titlecase(str_param(params, "name")?),
+        character_type: "Player".to_string(),
+        ac: int_param(params, "ac")?,
+        hp: encounter::PLAYER_HP,
+        max_hp: encounter::PLAYER_HP,
+        initiative: int_param(params, "initiative")?,
+        level: optional_int(params, "level")?.unwrap_or(0),
+        concentration: None,
+        conditions: Vec::new(),
+        surprised: false,
+        hidden: false,
+        joins_round: None,
+    };
+    let added = format!("Added Player/{} with initiative {}", character.name, character.initiative);
+    let mut characters = encounter::load_encounter_file();
+    characters.push(character);
+    let ids = encounter::save_added(&mut characters, &[added]);
+    Ok(json!(characters.iter().find(|character| Some(&character.id) == ids.first())))
+}
+
+///
+/// Removes a creature, ending anything it was concentrating on: {creature}
+///
+fn remove(params: &Value) -> Result<Value, RpcError> {
+    let mut characters = encounter::load_encounter_file();
+    let index = creature_param(params, "creature", &characters)?;
+    conditions::drop_concentration(&mut characters, index);
+    let character = characters.remove(index);
+    log::record_for(EventKind::Remove, &format!("Removed {}/{}", character.character_type, character.name), None, &[character.id]);
+    encounter::save_encounter_file(&mut characters);
+    Ok(json!(character))
+}
+
+///
+/// Damages or heals a creature: {creature, amount, concentration?}, where concentration is a player's save
+///
+fn damage(params: &Value, direction: i32) -> Result<Value, RpcError> {
+    let mut characters = encounter::load_encounter_file();
+    let index = creature_param(params, "creature", &characters)?;
+    let amount = int_param(params, "amount")? * direction;
+    let concentration_roll = optional_int(params, "concentration")?;
+    if amount > 0 && characters[index].concentration.is_some() {
+        require_player_roll(&characters[index], "con", concentration_roll, "concentration", "has to make a concentration save")?;
+    }
+
+    let result = encounter::apply_damage(&mut characters, index, amount, concentration_roll);
+    let character = characters[index].clone();
+    encounter::save_encounter_file(&mut characters);
+    Ok(json!({"creature": character, "concentration": result}))
+}
+
+///
+/// Rolls a monster's action against a creature: {attacker, target, action, save?, concentration?}.
+/// The action is its number or name, and save and concentration are rolls from a player target
+///
+fn attack(params: &Value) -> Result<Value, RpcError> {
+    let mut characters = encounter::load_encounter_file();
+    let attacker = creature_param(params, "attacker", &characters)?;
+    let target = creature_param(params, "target", &characters)?;
+    let statblock = stat_search::find_statblock(&characters[attacker].character_type)
+        .ok_or_else(|| invalid(format!("{} doesn't have a statblock to attack with", conditions::caster_label(&characters[attacker]))))?;
+
+    let action = match params.get("action") {
+        Some(Value::Number(number)) => number.as_u64().and_then(|number| statblock.actions.get((number as usize).wrapping_sub(1))),
+        Some(Value::String(name)) => statblock.actions.iter().find(|action| action.name.to_lowercase() == name.to_lowercase()),
+        _ => return Err(invalid("\"action\" must be an action number or name".to_string())),
+    }.cloned().ok_or_else(|| {
+        let names: Vec<&str> = statblock.actions.iter().map(|action| action.name.as_str()).collect();
+        invalid(format!("{} has no such action. Its actions are: {}", statblock.name, names.join(", ")))
+    })?;
+
+    let save_roll = optional_int(params, "save")?;
+    let concentration_roll = optional_int(params, "concentration")?;
+    if let Some(save) = &action.save {
+        require_player_roll(&characters[target], &save.ability, save_roll, "save", "has to make a saving throw")?;
+    }
+    if characters[target].concentration.is_some() {
+        require_player_roll(&characters[target], "con", concentration_roll, "concentration", "may have to make a concentration save")?;
+    }
+
+    let (lines, damage_dealt, changed) = encounter::resolve_attack(&mut characters, attacker, target, &action, save_roll, concentration_roll);
+    let character = characters[target].clone();
+    if changed {
+        encounter::save_encounter_file(&mut characters);
+    }
+    Ok(json!({"results": lines, "damage": damage_dealt, "target": character}))
+}
+
+///
+/// Moves the turn forwards or backwards, returning whose turn it is
+///
+fn advance(forward: bool) -> Result<Value, RpcError> {
+    let (state, notes) = turn::take_turn(forward).ok_or_else(|| invalid("The encounter is empty".to_string()))?;
+    let creature = encounter::load_encounter_file().swap_remove(state.position - 1);
+    Ok(json!({"round": state.round, "position": state.position, "creature": creature, "notes": notes}))
+}
+
+///
+/// Gives a creature a condition: {creature, condition, caster?}. A caster links it to the spell they're concentrating on
+///
+fn add_condition(params: &Value) -> Result<Value, RpcError> {
+    let mut characters = encounter::load_encounter_file();
+    let index = creature_param(params, "creature", &characters)?;
+    let name = titlecase(str_param(params, "condition")?);
+    let condition = match params.get("caster") {
+        Some(Value::Null) | None => {
+            log::record_for(EventKind::Condition, &format!("{} is now {}", conditions::caster_label(&characters[index]), name), None, &[characters[index].id]);
+            Condition { name, spell: None, caster: None, caster_id: None }
+        },
+        Some(..) => {
+            let caster = creature_param(params, "caster", &characters)?;
+            let spell = characters[caster].concentration.clone()
+                .ok_or_else(|| invalid(format!("{} isn't concentrating on a spell", conditions::caster_label(&characters[caster]))))?;
+            let caster_id = characters[caster].id;
+            let caster = conditions::caster_label(&characters[caster]);
+            log::record_for(EventKind::Condition, &format!("{} is {} from {}'s {}", conditions::caster_label(&characters[index]), name, caster, spell), None, &[characters[index].id, caster_id]);
+            Condition { name, spell: Some(spell), caster: Some(caster), caster_id: Some(caster_id) }
+        }
+    };
+    characters[index].conditions.push(condition);
+    let character = characters[index].clone();
+    encounter::save_encounter_file(&mut characters);
+    Ok(json!(character))
+}
+
+///
+/// Removes a condition from a creature by its name or number: {creature, condition}
+///
+fn remove_condition(params: &Value) -> Result<Value, RpcError> {
+    let mut characters = encounter::load_encounter_file();
+    let index = creature_param(params, "creature", &characters)?;
+    let position = match params.get("condition") {
+        Some(Value::Number(number)) => number.as_u64().map(|number| number as usize).filter(|&number| number >= 1 && number <= characters[index].conditions.len()).map(|number| number - 1),
+        Some(Value::String(name)) => characters[index].conditions.iter().position(|condition| condition.name.to_lowercase() == name.to_lowercase()),
+        _ => return Err(invalid("\"condition\" must be a condition number or name".to_string())),
+    }.ok_or_else(|| invalid(format!("{} doesn't have that condition", conditions::caster_label(&characters[index]))))?;
+
+    let removed = characters[index].conditions.remove(position);
+    log::record_for(EventKind::Condition, &format!("{} is no longer {}", conditions::caster_label(&characters[index]), removed.name), None, &[characters[index].id]);
+    let character = characters[index].clone();
+    encounter::save_encounter_file(&mut characters);
+    Ok(json!(character))
+}
+
+///
+/// Starts concentration on a spell, ending any previous one: {creature, spell}
+///
+fn concentrate(params: &Value) -> Result<Value, RpcError> {
+    let mut characters = encounter::load_encounter_file();
+    let index = creature_param(params, "creature", &characters)?;
+    let spell = titlecase(str_param(params, "spell")?);
+    conditions::drop_concentration(&mut characters, index);
+    characters[index].concentration = Some(spell.clone());
+    log::record_for(EventKind::Condition, &format!("{} started concentrating on {}", conditions::caster_label(&characters[index]), spell), None, &[characters[index].id]);
+    let character = characters[index].clone();
+    encounter::save_encounter_file(&mut characters);
+    Ok(json!(character))
+}
+
+///
+/// Ends a creature's concentration and the conditions linked to it: {creature}
+///
+fn drop_concentration(params: &Value) -> Result<Value, RpcError> {
+    let mut characters = encounter::load_encounter_file();
+    let index = creature_param(params, "creature", &characters)?;
+    if let Some(spell) = &characters[index].concentration {
+        log::record_for(EventKind::Condition, &format!("{} stopped concentrating on {}", conditions::caster_label(&characters[index]), spell), None, &[characters[index].id]);
+    }
+    conditions::drop_concentration(&mut characters, index);
+    let character = characters[index].clone();
+    encounter::save_encounter_file(&mut characters);
+    Ok(json!(character))
+}
+
+///
+/// Marks a creature as surprised, hidden or joining in a later round: {creature, surprised?, hidden?, joins_round?}.
+/// Setting hidden to false reveals it, and a joins_round that has already come brings it in straight away
+///
+fn mark(params: &Value) -> Result<Value, RpcError> {
+    let mut characters = encounter::load_encounter_file();
+    let index = creature_param(params, "creature", &characters)?;
+    let flag = |key: &str| match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value.as_bool().map(Some).ok_or_else(|| invalid(format!("\"{}\" must be true or false", key))),
+    };
+    let (surprised, hidden) = (flag("surprised")?, flag("hidden")?);
+    let joins = optional_int(params, "joins_round")?;
+    if joins.is_some_and(|joins| joins < 0) {
+        return Err(invalid("\"joins_round\" must be a round number".to_string()));
+    }
+
+    if let Some(surprised) = surprised {
+        surprise::set_surprised(&mut characters, index, surprised);
+    }
+    match hidden {
+        Some(true) => {surprise::hide(&mut characters, index);},
+        Some(false) => {encounter::reveal(&mut characters, index);},
+        None => {}
+    }
+    if let Some(joins) = joins {
+        surprise::set_joins_round(&mut characters, index, joins as usize, turn::load_turn().round);
+    }
+    let character = characters[index].clone();
+    encounter::save_encounter_file(&mut characters);
+    Ok(json!(character))
+}
+
+///
+/// Runs a single method with its parameters
+///
+fn call(method: &str, params: &Value) -> Result<Value, RpcError> {
+    match method {
+        "encounter.get" => Ok(encounter_state()),
+        "creature.add" => add(params),
+        "player.add" => add_player(params),
+        "creature.remove" => remove(params),
+        "creature.mark" => mark(params),
+        "creature.damage" => damage(params, 1),
+        "creature.heal" => damage(params, -1),
+        "attack" => attack(params),
+        "turn.next" => advance(true),
+        "turn.previous" => advance(false),
+        "condition.add" => add_condition(params),
+        "condition.remove" => remove_condition(params),
+        "concentration.start" => concentrate(params),
+        "concentration.drop" => drop_concentration(params),
+        "undo" => history::undo().map(|_| encounter_state()).map_err(invalid),
+        "redo" => history::redo().map(|_| encounter_state()).map_err(invalid),
+        _ => Err(RpcError { code: METHOD_NOT_FOUND, message: format!("Unknown method: {}", method) }),
+    }
+}
+
+///
+/// Builds an error response
+///
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": error.code, "message": error.message}})
+}
+
+///
+/// Handles one line of input, returning the response to send. Notifications, which have no id, don't get one
+///
+fn handle_line(line: &str) -> Option<Value> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return Some(error_response(Value::Null, RpcError { code: PARSE_ERROR, message: format!("Couldn't parse the request: {}", e) })),
+    };
+    let id = request.get("id").cloned();
+    let method = match request.get("method").and_then(|method| method.as_str()) {
+        Some(method) if request.get("jsonrpc") == Some(&json!("2.0")) => method,
+        _ => return Some(error_response(id.unwrap_or(Value::Null), RpcError { code: INVALID_REQUEST, message: "Requests need \"jsonrpc\": \"2.0\" and a method".to_string() })),
+    };
+    let params = match request.get("params") {
+        None | Some(Value::Null) => json!({}),
+        Some(params) if params.is_object() => params.clone(),
+        Some(..) => return Some(error_response(id.unwrap_or(Value::Null), invalid("\"params\" must be an object".to_string()))),
+    };
+
+    let result = {
+        let _lock = REQUEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        call(method, &params)
+    };
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err(error) => error_response(id, error),
+    })
+}
+
+///
+/// Writes a message as a single line
+///
+fn send(output: &Output, message: &Value) -> io::Result<()> {
+    let mut output = output.lock().unwrap_or_else(|e| e.into_inner());
+    writeln!(output, "{}", message)?;
+    output.flush()
+}
+
+///
+/// Sends an encounter.changed event if the encounter or turn has changed since the last one this connection saw
+///
+fn notify_changes(output: &Output, last: &Mutex<Value>) -> io::Result<()> {
+    let current = encounter_state();
+    let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+    if *last != current {
+        send(output, &json!({"jsonrpc": "2.0", "method": "encounter.changed", "params": current}))?;
+        *last = current;
+    }
+    Ok(())
+}
+
+///
+/// Answers requests until the input ends. Change events are sent after each request and whenever the encounter
+/// files change, so clients also hear about changes made from the menu, the TUI or other connections
+///
+fn session(input: impl BufRead, output: Output) {
+    let last = Arc::new(Mutex::new(encounter_state()));
+    let closed = Arc::new(AtomicBool::new(false));
+    {
+        let (output, last, closed) = (output.clone(), last.clone(), closed.clone());
+        thread::spawn(move || {
+            while !closed.load(Ordering::SeqCst) {
+                thread::sleep(POLL_INTERVAL);
+                if notify_changes(&output, &last).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(..) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(&line) {
+            if send(&output, &response).is_err() {
+                break;
+            }
+        }
+        if notify_changes(&output, &last).is_err() {
+            break;
+        }
+    }
+    closed.store(true, Ordering::SeqCst);
+}
+
+///
+/// Speaks the protocol over stdin and stdout, one JSON message per line
+///
+pub fn serve_stdio() {
+    let output: Output = Arc::new(Mutex::new(Box::new(io::stdout())));
+    session(io::stdin().lock(), output);
+}
+
+///
+/// Listens on a Unix socket, handling each connection on its own thread. A socket left behind by an earlier run is replaced
+///
+pub fn serve_socket(path: &str) -> io::Result<()> {
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists and isn't a socket", path)));
+        }
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    eprintln!("Listening on {}. Press Ctrl-C to stop", path);
+    for stream in listener.incoming().flatten() {
+        thread::spawn(move || {
+            let writer = match stream.try_clone() {
+                Ok(writer) => writer,
+                Err(..) => return,
+            };
+            session(BufReader::new(stream), Arc::new(Mutex::new(Box::new(writer))));
+        });
+    }
+    Ok(())
+}
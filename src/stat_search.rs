@@ -1,10 +1,9 @@
-use std::fs;
-use serde_json::Result;
+use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 use crate::user_input;
-use std::io;
-use std::io::Write;
 use crate::encounter;
+use crate::render;
+use crate::validation;
 use crate::encounter::Character;
 use rand::Rng;
 use titlecase::titlecase;
@@ -12,139 +11,250 @@ use titlecase::titlecase;
 ///
 /// Creature struct used for storing all character stats
 ///
-#[derive(Serialize, Deserialize, Debug)]
-struct Creature {
-    name: String,
-    health: i32,
-    armor_class: i32,
-    initiative: i32,
-    movement_speed: i32,
-    str: i32,
-    dex: i32,
-    con: i32,
-    int: i32,
-    wis: i32,
-    cha: i32,
-    actions: Vec<Action>,
-    abilities: Vec<Ability>,
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Creature {
+    pub name: String,
+    #[serde(default)]
+    pub size: String,
+    #[serde(default)]
+    pub creature_type: String,
+    pub health: i32,
+    pub armor_class: i32,
+    pub initiative: i32,
+    pub movement_speed: i32,
+    #[serde(default)]
+    pub challenge_rating: String,
+    #[serde(default)]
+    pub xp: i32,
+    pub str: i32,
+    pub dex: i32,
+    pub con: i32,
+    pub int: i32,
+    pub wis: i32,
+    pub cha: i32,
+    #[serde(default = "default_proficiency_bonus")]
+    pub proficiency_bonus: i32,
+    #[serde(default)]
+    pub saving_throws: Vec<String>,
+    #[serde(default)]
+    pub skills: Vec<String>,
+    pub actions: Vec<Action>,
+    pub abilities: Vec<Ability>,
+    #[serde(default)]
+    pub loot: Vec<Loot>,
 }
 
 ///
 /// Action struct used for storing attack action information
 ///
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Action {
-    name: String,
-    description: String,
+    pub name: String,
+    pub description: String,
     pub attack_modifier: i32,
     pub damage_dice: Vec<i32>,
     pub damage_bonus: i32,
     pub damage_type: String,
+    #[serde(default)]
+    pub save: Option<ActionSave>,
+}
+
+///
+/// ActionSave struct used for actions that force a saving throw instead of rolling to hit
+///
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ActionSave {
+    pub ability: String,
+    pub dc: i32,
+    #[serde(default)]
+    pub half_on_success: bool,
+}
+
+///
+/// Loot struct used for items a monster can drop when it's defeated
+///
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Loot {
+    pub name: String,
+    #[serde(default = "default_loot_quantity")]
+    pub quantity: String,
+    #[serde(default = "default_loot_chance")]
+    pub chance: i32,
+}
+
+///
+/// System-wide statblock file installed with the tracker
+///
+pub const STATBLOCK_PATH: &str = "/usr/local/share/dnd-encounter-tracker/statblocks.json";
+
+///
+/// Abilities in statblock order, used for saving throws and raw ability checks
+///
+pub const ABILITIES: [&str; 6] = ["str", "dex", "con", "int", "wis", "cha"];
+
+///
+/// Full names of the abilities, in the same order as [ABILITIES]
+///
+const ABILITY_NAMES: [&str; 6] = ["strength", "dexterity", "constitution", "intelligence", "wisdom", "charisma"];
+
+///
+/// Skills and the ability each one is rolled with
+///
+pub const SKILLS: [(&str, &str); 18] = [
+    ("acrobatics", "dex"),
+    ("animal handling", "wis"),
+    ("arcana", "int"),
+    ("athletics", "str"),
+    ("deception", "cha"),
+    ("history", "int"),
+    ("insight", "wis"),
+    ("intimidation", "cha"),
+    ("investigation", "int"),
+    ("medicine", "wis"),
+    ("nature", "int"),
+    ("perception", "wis"),
+    ("performance", "cha"),
+    ("persuasion", "cha"),
+    ("religion", "int"),
+    ("sleight of hand", "dex"),
+    ("stealth", "dex"),
+    ("survival", "wis"),
+];
+
+///
+/// Proficiency bonus used when a statblock doesn't list one (the value for CR 0-4)
+///
+fn default_proficiency_bonus() -> i32 {
+    2
 }
 
 ///
 /// Ability struct used for storing other ability information
 ///
-#[derive(Serialize, Deserialize, Debug)]
-struct Ability {
-    name: String,
-    description: String,
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Ability {
+    pub name: String,
+    pub description: String,
 }
 
 ///
 /// Loads monster stats to be used in [encounter]
 ///
 pub fn load_monster(monster_type: String) -> encounter::Character {
-    // Reads statblock file
-    let contents = fs::read_to_string("/usr/local/share/dnd-encounter-tracker/statblocks.json").expect("Couldn't read statblock file");
-    let creatures = parse_json(&contents).unwrap();
+    // Reads statblock and homebrew files
+    let creatures = load_statblocks();
 
-    // Checks if the creature exists
-    if let Some(creature) = creatures.iter().find(|c| c.name.to_lowercase() == monster_type.to_lowercase()) {
+    // Checks if the creature exists, offering close matches for typos
+    let monster_name = pick_monster(&monster_type).unwrap_or_default();
+    if let Some(creature) = creatures.iter().find(|c| c.name == monster_name) {
         println!("\nEnter a name for the {}: ", creature.name);
         let name = titlecase(&user_input::input());
-        let character_type = &creature.name;
-        let ac = creature.armor_class;
-        let hp = creature.health;
-
-        // Uses RNG to roll for initiative
-        let mut initiative = rand::thread_rng().gen_range(1..21) + creature.initiative;
-
-        // Ensures initiative doesn't drop below 1
-        if initiative <= 0 {initiative = 1}
-        println!("\nAdded {}/{}, with rolled initiative {}\n", character_type, name, initiative);
-        Character {
-            name,
-            character_type: character_type.to_string(),
-            ac,
-            hp,
-            initiative,
-        }
+        let character = create_monster(&creature.name, name).unwrap();
+        println!("\nAdded {}/{}, with rolled initiative {}\n", character.character_type, character.name, character.initiative);
+        character
     } else {
         // Restarts function if the monster doesn't exist in the statblock file
-        println!("\nInvalid monster! Use one of the below monsters:");
-        print_monsters();
-        println!("\nEnter monster type (type ls for a list of monsters):");
-        load_monster(user_input::input())
+        println!("\nInvalid monster! Enter monster type (type ls for a list of monsters):");
+        let input = user_input::input();
+        if input == "ls" {
+            println!();
+            print_monsters();
+            println!("\nEnter monster type:");
+            return load_monster(user_input::input());
+        }
+        load_monster(input)
     }
 }
 
+///
+/// Rolls initiative for a creature, making sure it doesn't drop below 1
+///
+pub fn roll_initiative(creature: &Creature) -> i32 {
+    std::cmp::max(rand::thread_rng().gen_range(1..21) + creature.initiative, 1)
+}
+
+///
+/// Creates a named monster from its statblock with rolled initiative, or None if the monster doesn't exist
+///
+pub fn create_monster(monster_type: &str, name: String) -> Option<Character> {
+    let creatures = load_statblocks();
+    let creature = creatures.iter().find(|c| c.name.to_lowercase() == monster_type.to_lowercase())?;
+
+    let initiative = roll_initiative(creature);
+    Some(Character {
+        id: 0,
+        name,
+        character_type: creature.name.clone(),
+        ac: creature.armor_class,
+        hp: creature.health,
+        max_hp: creature.health,
+        initiative,
+        level: 0,
+        concentration: None,
+        conditions: Vec::new(),
+        surprised: false,
+        hidden: false,
+        joins_round: None,
+    })
+}
+
 ///
 /// Prints available monsters in the statblock file
 ///
 pub fn print_monsters() {
-    let contents = fs::read_to_string("/usr/local/share/dnd-encounter-tracker/statblocks.json").expect("Couldn't read statblock file");
-    let creatures = parse_json(&contents).unwrap();
-    println!("{}", format!("╔{:═<70}╗", "═"));
-    println!("{}", format!("║{:^70}║", "Available creatures:"));
-    println!("{}", format!("╙{:─<70}╜", "─"));
-    print!("│ ");
-    for creature in &creatures {
-        print!("{} │ ", creature.name);
-        io::stdout().flush().unwrap();
+    let creatures = load_statblocks();
+    let width = render::width();
+    render::top(width);
+    render::centered("Available creatures:", width);
+    render::open_bottom(width);
+
+    // Wraps the list of names so it doesn't run past the edge of the terminal
+    let names: Vec<&str> = creatures.iter().map(|creature| creature.name.as_str()).collect();
+    for line in render::wrap(&format!("│ {} │", names.join(" │ ")), width + 2) {
+        println!("{}", line);
     }
-    println!("\n{}", format!("{:═^72}", "═"));
+    render::rule(width, "═");
 }
 
 ///
 /// Used in the main menu to display selected monster's actions and abilities
 ///
 pub fn combat_stats(creature_stat: &str) {
-    let contents = fs::read_to_string("/usr/local/share/dnd-encounter-tracker/statblocks.json").expect("Couldn't read statblock file");
-    let creatures = parse_json(&contents).unwrap();
+    let creatures = load_statblocks();
+    let width = render::width();
 
     if let Some(creature) = creatures.iter().find(|c| c.name.to_lowercase() == creature_stat.to_lowercase()) {
-    println!("\n{}", format!("╔{:═^70}╗", "═"));
-    println!("{}", format!("║{:^70}║", format!("Actions:")));
-    // Variable used for printing box for first creature
-    let mut num = 1;
-    for action in &creature.actions {
-        if num == 1 {
-            println!("{}", format!("╙{:─<70}╜", "─"));
+    println!();
+    render::top(width);
+    render::centered("Actions:", width);
+    for (num, action) in creature.actions.iter().enumerate() {
+        // Closes the heading box above the first action, and separates the rest with a line
+        if num == 0 {
+            render::open_bottom(width);
         } else {
-            println!("{}", format!("{:─<72}", "─"));
+            render::rule(width, "─");
         }
-        num+=1;
         println!(" {}:", action.name);
-        println!("Description: \"{}\"", action.description);
+        render::paragraph(&format!("Description: \"{}\"", action.description), width);
         println!("Attack roll modifier: +{}", action.attack_modifier);
         println!("Damage dice: {}d{}+{}", action.damage_dice[0], action.damage_dice[1], action.damage_bonus);
         println!("Damage type: {}", action.damage_type);
+        if let Some(save) = &action.save {
+            println!("Saving throw: DC {} {}{}", save.dc, save.ability.to_uppercase(), if save.half_on_success {", half damage on success"} else {""});
+        }
     }
-    println!("{}", format!("╔{:═^70}╗", "═"));
-    println!("{}", format!("║{:^70}║", format!("Abilities:")));
-    num = 1;
-    for ability in &creature.abilities {
-        if num == 1 {
-            println!("{}", format!("╙{:─<70}╜", "─"));
+    render::top(width);
+    render::centered("Abilities:", width);
+    for (num, ability) in creature.abilities.iter().enumerate() {
+        if num == 0 {
+            render::open_bottom(width);
         } else {
-            println!("{}", format!("{:─<72}", "─"));
+            render::rule(width, "─");
         }
-        num+=1;
         println!(" {}:", ability.name);
-        println!("Description: \"{}\"", ability.description);
+        render::paragraph(&format!("Description: \"{}\"", ability.description), width);
     }
-    println!("{}", format!("{:═^72}", "═"));
+    render::rule(width, "═");
     } else {
         println!("\nCreature not found.\n");
     }
@@ -154,11 +264,10 @@ pub fn combat_stats(creature_stat: &str) {
 /// Used in the main menu to display selected monster's stats
 ///
 pub fn print_attributes(creature_stat: &str) {
-    let contents = fs::read_to_string("/usr/local/share/dnd-encounter-tracker/statblocks.json").expect("Couldn't read statblock file");
-    let creatures = parse_json(&contents).unwrap();
+    let creatures = load_statblocks();
 
     if let Some(creature) = creatures.iter().find(|c| c.name.to_lowercase() == creature_stat.to_lowercase()) {
-        println!("{}", format!("║{:^11}│{:^11}│{:^11}│{:^11}│{:^11}│{:^10}║", format!("STR: {}", creature.str), format!("DEX: {}", creature.dex), format!("CON: {}", creature.con), format!("INT: {}", creature.int), format!("WIS: {}", creature.wis), format!("CHA: {}", creature.cha)));
+        render::columns(&ability_row(creature), render::width());
     } 
 }
 
@@ -166,23 +275,35 @@ pub fn print_attributes(creature_stat: &str) {
 /// Used in [encounter::attack] to display selected monster's attacks
 ///
 pub fn print_attacks(creature_stat: &str) -> usize {
-    let contents = fs::read_to_string("/usr/local/share/dnd-encounter-tracker/statblocks.json").expect("Couldn't read statblock file");
-    let creatures = parse_json(&contents).unwrap();
+    let creatures = load_statblocks();
 
     if let Some(creature) = creatures.iter().find(|c| c.name.to_lowercase() == creature_stat.to_lowercase()) {
-        let mut number = 1;
-        println!("\n{}", format!("╔{:═^35}╗", "═"));
-        for action in &creature.actions {
-            println!("{}", format!("║{:^35}║", format!("{}. {}", number, action.name)));
-            println!("{}", format!("║{:^35}║", format!("Attack modifier: {}", action.attack_modifier)));
-            println!("{}", format!("║{:^35}║", format!("Damage: {}d{}+{} {} damage", action.damage_dice[0], action.damage_dice[1], action.damage_bonus, action.damage_type)));
-            if number != creature.actions.len(){
-                println!("{}", format!("╟{:─<35}╢", "─"));
-            }
+        // Builds each attack's lines first so the box can be sized to fit them
+        let attacks: Vec<Vec<String>> = creature.actions.iter().enumerate().map(|(number, action)| {
+            let roll = match &action.save {
+                Some(save) => format!("Saving throw: DC {} {}", save.dc, save.ability.to_uppercase()),
+                None => format!("Attack modifier: {}", action.attack_modifier),
+            };
+            vec![
+                format!("{}. {}", number + 1, action.name),
+                roll,
+                format!("Damage: {}d{}+{} {} damage", action.damage_dice[0], action.damage_dice[1], action.damage_bonus, action.damage_type),
+            ]
+        }).collect();
+        let width = render::fit_width(&attacks.concat());
 
-            number += 1;
+        println!();
+        render::top(width);
+        for (number, attack) in attacks.iter().enumerate() {
+            for line in attack {
+                render::centered(line, width);
+            }
+            if number + 1 != attacks.len() {
+                render::divider(width);
+            }
         }
-        println!("{}\n", format!("╚{:═^35}╝", "═"));
+        render::bottom(width);
+        println!();
         creature.actions.len()
     } else {0}
 }
@@ -191,8 +312,7 @@ pub fn print_attacks(creature_stat: &str) -> usize {
 /// Used to send the selected attack to [encounter::attack], returning null values if the attack or monster doesn't exist
 ///
 pub fn get_attack(creature_stat: &str, attack_number: usize) -> Action {
-    let contents = fs::read_to_string("/usr/local/share/dnd-encounter-tracker/statblocks.json").expect("Couldn't read statblock file");
-    let creatures = parse_json(&contents).unwrap();
+    let creatures = load_statblocks();
 
     if let Some(creature) = creatures.iter().find(|c| c.name.to_lowercase() == creature_stat.to_lowercase()) {
         if attack_number > creature.actions.len() {
@@ -203,6 +323,7 @@ pub fn get_attack(creature_stat: &str, attack_number: usize) -> Action {
                 damage_dice: [0, 0].to_vec(),
                 damage_bonus: 0,
                 damage_type: "Null".to_string(),
+                save: None,
             }
         } else {
             Action {
@@ -212,6 +333,7 @@ pub fn get_attack(creature_stat: &str, attack_number: usize) -> Action {
                 damage_dice: creature.actions[attack_number-1].damage_dice.clone(),
                 damage_bonus: creature.actions[attack_number-1].damage_bonus,
                 damage_type: creature.actions[attack_number-1].damage_type.clone(),
+                save: creature.actions[attack_number-1].save.clone(),
             }
         }
     } else {
@@ -222,47 +344,525 @@ pub fn get_attack(creature_stat: &str, attack_number: usize) -> Action {
             damage_dice: [0, 0].to_vec(),
             damage_bonus: 0,
             damage_type: "Null".to_string(),
+            save: None,
         }
     }
 }
 
 ///
-/// Function used to display the monster's statblock
+/// Builds a monster's statblock as plain lines of text, used by the full-screen interface
+///
+pub fn statblock_lines(creature_stat: &str) -> Vec<String> {
+    let creatures = load_statblocks();
+    let creature = match creatures.iter().find(|c| c.name.to_lowercase() == creature_stat.to_lowercase()) {
+        Some(creature) => creature,
+        None => return Vec::new(),
+    };
+
+    let mut lines = vec![
+        format!("{} {}", creature.size, creature.creature_type.to_lowercase()).trim().to_string(),
+        format!("AC {} | HP {} | Speed {} ft. | CR {} ({} XP)", creature.armor_class, creature.health, creature.movement_speed, creature.challenge_rating, creature.xp),
+        format!("{} | {} | {}", ability_string("STR", creature.str), ability_string("DEX", creature.dex), ability_string("CON", creature.con)),
+        format!("{} | {} | {}", ability_string("INT", creature.int), ability_string("WIS", creature.wis), ability_string("CHA", creature.cha)),
+    ];
+    let saves = save_strings(creature);
+    if !saves.is_empty() {
+        lines.push(format!("Saving throws: {}", saves.join(", ")));
+    }
+    let skills = skill_strings(creature);
+    if !skills.is_empty() {
+        lines.push(format!("Skills: {}", skills.join(", ")));
+    }
+
+    lines.push(String::new());
+    lines.push("Actions:".to_string());
+    for action in &creature.actions {
+        let roll = match &action.save {
+            Some(save) => format!("DC {} {} save", save.dc, save.ability.to_uppercase()),
+            None => format!("{:+} to hit", action.attack_modifier),
+        };
+        lines.push(format!("{} ({}, {}d{}+{} {}): {}", action.name, roll, action.damage_dice[0], action.damage_dice[1], action.damage_bonus, action.damage_type, action.description));
+    }
+    lines.push(String::new());
+    lines.push("Abilities:".to_string());
+    for ability in &creature.abilities {
+        lines.push(format!("{}: {}", ability.name, ability.description));
+    }
+    lines
+}
+
+///
+/// Prints a creature's statblock, including its actions and abilities
+///
+fn print_statblock(creature: &Creature) {
+    let width = render::width();
+    println!();
+    render::top(width);
+    render::centered(&format!("Stats for {}:", creature.name), width);
+    if !creature.size.is_empty() {
+        render::centered(&format!("{} {}", creature.size, creature.creature_type.to_lowercase()), width);
+    }
+    render::divider(width);
+    render::centered(&format!("Health: {}", creature.health), width);
+    render::dotted(width);
+    render::centered(&format!("Armor class: {}", creature.armor_class), width);
+    render::dotted(width);
+    render::centered(&format!("Initiative: {}", creature.initiative), width);
+    render::dotted(width);
+    render::centered(&format!("Movement Speed: {}", creature.movement_speed), width);
+    render::dotted(width);
+    render::centered(&format!("Challenge: {} ({} XP)", creature.challenge_rating, creature.xp), width);
+    render::dotted(width);
+    render::columns(&ability_row(creature), width);
+    render::dotted(width);
+    render::centered(&format!("Proficiency bonus: +{}", creature.proficiency_bonus), width);
+    let saves = save_strings(creature);
+    if !saves.is_empty() {
+        render::centered(&format!("Saving throws: {}", saves.join(", ")), width);
+    }
+    let skills = skill_strings(creature);
+    if !skills.is_empty() {
+        render::centered(&format!("Skills: {}", skills.join(", ")), width);
+    }
+    render::centered(&format!("Passive Perception: {}", passive_perception(creature)), width);
+    render::bottom(width);
+    println!();
+    combat_stats(&creature.name);
+    println!();
+}
+
+///
+/// Function used to browse the statblock library, searching by name or filtering by stats
 ///
 pub fn statblocks() {
-    let contents = fs::read_to_string("/usr/local/share/dnd-encounter-tracker/statblocks.json").expect("Couldn't read statblock file");
-    let creatures = parse_json(&contents).unwrap();
-
-    print_monsters();
-
-    println!("\nEnter a creature name to get its stats:");
-    let name = user_input::input();
-
-    if let Some(creature) = creatures.iter().find(|c| c.name.to_lowercase() == name.to_lowercase()) {
-        println!("\n{}", format!("╔{:═^70}╗", "═"));
-        println!("{}", format!("║{:^70}║", format!("Stats for {}:", creature.name)));
-        println!("{}", format!("╟{:─<70}╢", "─"));
-        println!("{}", format!("║{:^70}║", format!("Health: {}:", creature.health)));
-        println!("{}", format!("╟{:┄<70}╢", "┄"));
-        println!("{}", format!("║{:^70}║", format!("Armor class: {}:", creature.armor_class)));
-        println!("{}", format!("╟{:┄<70}╢", "┄"));
-        println!("{}", format!("║{:^70}║", format!("Initiative: {}:", creature.initiative)));
-        println!("{}", format!("╟{:┄<70}╢", "┄"));
-        println!("{}", format!("║{:^70}║", format!("Movement Speed: {}:", creature.movement_speed)));
-        println!("{}", format!("╟{:┄<70}╢", "┄"));
-        println!("{}", format!("║{:^11}│{:^11}│{:^11}│{:^11}│{:^11}│{:^10}║", format!("STR: {}", creature.str), format!("DEX: {}", creature.dex), format!("CON: {}", creature.con), format!("INT: {}", creature.int), format!("WIS: {}", creature.wis), format!("CHA: {}", creature.cha)));
-        println!("{}\n", format!("╚{:═<70}╝", "═"));
-        combat_stats(&name);
+    let creatures = load_statblocks();
+
+    loop {
+        println!("Enter a creature name to get its stats, (f)ilter creatures, type ls for a list of creatures, or press enter to return:");
+        let input = user_input::input();
         println!();
+        match input.as_str() {
+            "" | "done" => break,
+            "ls" => {print_monsters(); println!();},
+            "f" => {
+                println!("Enter filters separated by spaces, e.g. \"cr:1-3 type:humanoid ranged\":");
+                println!("cr:<rating or range> type:<type> size:<size> speed:<minimum> damage:<type> has:<text in an action or ability>\n");
+                let filters = user_input::input();
+                println!();
+                match filter_creatures(&creatures, &filters) {
+                    Ok(matches) => print_creature_list(&matches),
+                    Err(e) => println!("{}\n", e),
+                }
+            },
+            _ => {
+                if let Some(name) = pick_monster(&input) {
+                    if let Some(creature) = creatures.iter().find(|c| c.name == name) {
+                        print_statblock(creature);
+                    }
+                } else {
+                    println!("Creature not found.\n");
+                }
+            },
+        }
+    }
+}
+
+///
+/// Prints a one line summary of each creature in a list, used for filter results
+///
+fn print_creature_list(creatures: &[&Creature]) {
+    if creatures.is_empty() {
+        println!("No creatures match those filters.\n");
+        return;
+    }
+    let lines: Vec<String> = creatures.iter().map(|creature| format!("{}: {} {}, CR {}, speed {} ft.", creature.name, creature.size, creature.creature_type.to_lowercase(), creature.challenge_rating, creature.movement_speed)).collect();
+    let width = render::fit_width(&lines);
+    render::top(width);
+    render::centered(&format!("Matching creatures ({}):", creatures.len()), width);
+    render::divider(width);
+    for line in &lines {
+        render::centered(line, width);
+    }
+    render::bottom(width);
+    println!();
+}
+
+///
+/// Converts a challenge rating like "1/2" or "3" into a number so ratings can be compared
+///
+pub fn challenge_value(rating: &str) -> Option<f32> {
+    match rating.trim().split_once('/') {
+        Some((top, bottom)) => Some(top.parse::<f32>().ok()? / bottom.parse::<f32>().ok()?),
+        None => rating.trim().parse().ok(),
+    }
+}
+
+///
+/// Checks whether any of a creature's actions or abilities mention some text
+///
+fn creature_has(creature: &Creature, text: &str) -> bool {
+    creature.actions.iter().any(|action| action.name.to_lowercase().contains(text) || action.description.to_lowercase().contains(text))
+        || creature.abilities.iter().any(|ability| ability.name.to_lowercase().contains(text) || ability.description.to_lowercase().contains(text))
+}
+
+///
+/// Checks a creature against a single filter term, returning an error for unknown filters
+///
+fn matches_filter(creature: &Creature, term: &str) -> std::result::Result<bool, String> {
+    let (key, value) = match term.split_once(':') {
+        Some((key, value)) => (key, value),
+        // Bare words match the name, type or anything the creature can do
+        None => return Ok(creature.name.to_lowercase().contains(term) || creature.creature_type.to_lowercase().contains(term) || creature_has(creature, term)),
+    };
+    let rating = challenge_value(&creature.challenge_rating);
+    match key {
+        "cr" => {
+            let (low, high) = match value.split_once('-') {
+                Some((low, high)) => (challenge_value(low), challenge_value(high)),
+                None => (challenge_value(value), challenge_value(value)),
+            };
+            match (low, high, rating) {
+                (Some(low), Some(high), Some(rating)) => Ok(rating >= low && rating <= high),
+                (Some(..), Some(..), None) => Ok(false),
+                _ => Err(format!("Invalid challenge rating: {}", value)),
+            }
+        },
+        "type" => Ok(creature.creature_type.to_lowercase().starts_with(value)),
+        "size" => Ok(creature.size.to_lowercase().starts_with(value)),
+        "speed" => match value.parse::<i32>() {
+            Ok(speed) => Ok(creature.movement_speed >= speed),
+            Err(..) => Err(format!("Invalid speed: {}", value)),
+        },
+        "damage" => Ok(creature.actions.iter().any(|action| action.damage_type.to_lowercase().contains(value))),
+        "has" => Ok(creature_has(creature, value)),
+        _ => Err(format!("Unknown filter: {}", key)),
+    }
+}
+
+///
+/// Finds every creature that matches all of the filter terms
+///
+fn filter_creatures<'a>(creatures: &'a [Creature], filters: &str) -> std::result::Result<Vec<&'a Creature>, String> {
+    let mut matches = Vec::new();
+    for creature in creatures {
+        let mut keep = true;
+        for term in filters.to_lowercase().split_whitespace() {
+            keep = keep && matches_filter(creature, term)?;
+        }
+        if keep {
+            matches.push(creature);
+        }
+    }
+    Ok(matches)
+}
+
+///
+/// Counts the single character edits needed to turn one string into another
+///
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let cost = if a_char == *b_char {0} else {1};
+            current.push(std::cmp::min(std::cmp::min(previous[j + 1] + 1, current[j] + 1), previous[j] + cost));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+///
+/// Lists the monsters closest to a misspelled or partial name, best matches first
+///
+pub fn suggest_monsters(query: &str) -> Vec<String> {
+    let query = query.trim().to_lowercase();
+    let mut scored: Vec<(usize, String)> = monster_names().into_iter().filter_map(|name| {
+        let lower = name.to_lowercase();
+        // Partial names count as close matches, otherwise a few typos are allowed depending on length
+        if !query.is_empty() && lower.contains(&query) {
+            Some((0, name))
+        } else {
+            let distance = edit_distance(&query, &lower);
+            if distance <= std::cmp::max(2, query.len() / 3) {Some((distance, name))} else {None}
+        }
+    }).collect();
+    scored.sort();
+
+    // Partial matches are much more likely to be what was meant than typos, so they hide the rest
+    if scored.first().is_some_and(|(distance, _)| *distance == 0) {
+        scored.retain(|(distance, _)| *distance == 0);
+    }
+    scored.into_iter().take(5).map(|(_, name)| name).collect()
+}
+
+///
+/// Finds a monster by name, asking "did you mean" when there's no exact match. Returns None if nothing is picked
+///
+pub fn pick_monster(query: &str) -> Option<String> {
+    if let Some(name) = monster_names().into_iter().find(|name| name.to_lowercase() == query.trim().to_lowercase()) {
+        return Some(name);
+    }
+    let suggestions = suggest_monsters(query);
+    match suggestions.len() {
+        0 => None,
+        1 => {
+            println!("Did you mean {}? (y/n)", suggestions[0]);
+            let answer = user_input::input();
+            println!();
+            if answer == "y" {suggestions.into_iter().next()} else {None}
+        },
+        _ => {
+            println!("Did you mean one of these?");
+            for (number, name) in suggestions.iter().enumerate() {
+                println!("{}. {}", number + 1, name);
+            }
+            println!("Enter a number, or type \"0\" if none of them:");
+            let choice = user_input::usize_input();
+            println!();
+            if choice == 0 || choice > suggestions.len() {None} else {Some(suggestions[choice - 1].clone())}
+        },
+    }
+}
+
+///
+/// Reads the valid creatures from a statblock file, or None if the file can't be read
+///
+fn read_statblock_file(path: &str) -> Option<Vec<Creature>> {
+    // Invalid entries are skipped here, and reported by [validation::check_library]
+    validation::check_file(path).map(|(creatures, _)| creatures)
+}
+
+///
+/// Path of the user's homebrew statblock file
+///
+pub fn homebrew_path() -> PathBuf {
+    PathBuf::from(shellexpand::tilde("~/.config/dnd-encounter-tracker/homebrew.json").into_owned())
+}
+
+///
+/// Loads the user's valid homebrew creatures for the library, or none if the file doesn't exist yet. Invalid ones are
+/// reported by [validation::check_library], and the file can't be saved over until they're fixed (see [read_homebrew])
+///
+pub fn load_homebrew() -> Vec<Creature> {
+    read_statblock_file(&homebrew_path().to_string_lossy()).unwrap_or_default()
+}
+
+///
+/// Reads every homebrew creature so the file can be changed and saved, or none if the file doesn't exist yet.
+/// Returns an error if any entry is invalid or the file can't be parsed, since saving would throw those entries away
+///
+pub fn read_homebrew() -> Result<Vec<Creature>, String> {
+    let path = homebrew_path();
+    match validation::check_file(&path.to_string_lossy()) {
+        None => Ok(Vec::new()),
+        Some((creatures, problems)) if problems.is_empty() => Ok(creatures),
+        Some((_, problems)) => {
+            let problems: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+            Err(format!("{} has problems, so it won't be saved over until they're fixed:\n{}", path.display(), problems.join("\n")))
+        }
+    }
+}
+
+///
+/// Saves homebrew creatures to ~/.config/dnd-encounter-tracker/homebrew.json and creates the directory if it doesn't exist
+///
+pub fn save_homebrew(creatures: &[Creature]) {
+    let json_creatures = serde_json::to_string_pretty(creatures).unwrap();
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker").into_owned();
+    let path = PathBuf::from(expanded_path);
+    std::fs::create_dir_all(&path).expect("Failed to create directory");
+    std::fs::write(homebrew_path(), json_creatures).expect("Unable to write to file");
+}
+
+///
+/// Adds homebrew creatures to the library, replacing any built-in creature with the same name
+///
+fn merge_homebrew(mut creatures: Vec<Creature>) -> Vec<Creature> {
+    for homebrew in load_homebrew() {
+        match creatures.iter().position(|c| c.name.to_lowercase() == homebrew.name.to_lowercase()) {
+            Some(index) => creatures[index] = homebrew,
+            None => creatures.push(homebrew),
+        }
+    }
+    creatures
+}
+
+///
+/// Loads every creature from the statblock file along with the user's homebrew creatures
+///
+pub fn load_statblocks() -> Vec<Creature> {
+    let creatures = read_statblock_file(STATBLOCK_PATH).expect("Couldn't read statblock file");
+    merge_homebrew(creatures)
+}
+
+///
+/// Lists the names of every monster in the library, or only the homebrew ones if the statblock file can't be read
+///
+pub fn monster_names() -> Vec<String> {
+    let creatures = read_statblock_file(STATBLOCK_PATH).unwrap_or_default();
+    merge_homebrew(creatures).into_iter().map(|creature| creature.name).collect()
+}
+
+///
+/// Converts an ability score into its modifier (e.g. 16 becomes +3)
+///
+pub fn ability_modifier(score: i32) -> i32 {
+    (score - 10).div_euclid(2)
+}
+
+///
+/// Formats an ability score with its modifier, e.g. "STR 16 (+3)"
+///
+fn ability_string(ability: &str, score: i32) -> String {
+    format!("{} {} ({:+})", ability, score, ability_modifier(score))
+}
+
+///
+/// Formats a creature's proficient saving throws with their modifiers (e.g. "Con +5")
+///
+pub fn save_strings(creature: &Creature) -> Vec<String> {
+    creature.saving_throws.iter().filter_map(|save| ability_key(save).map(|key| format!("{} {:+}", titlecase(key), save_modifier(creature, key)))).collect()
+}
+
+///
+/// Formats a creature's proficient skills with their modifiers (e.g. "Stealth +4")
+///
+pub fn skill_strings(creature: &Creature) -> Vec<String> {
+    creature.skills.iter().filter_map(|skill| check_modifier(creature, skill).map(|modifier| format!("{} {:+}", titlecase(skill), modifier))).collect()
+}
+
+///
+/// Gets a creature's passive Perception score
+///
+pub fn passive_perception(creature: &Creature) -> i32 {
+    10 + check_modifier(creature, "perception").unwrap_or(0)
+}
+
+///
+/// Finds a creature's statblock by name, including homebrew creatures
+///
+pub fn find_statblock(creature_stat: &str) -> Option<Creature> {
+    load_statblocks().into_iter().find(|c| c.name.to_lowercase() == creature_stat.to_lowercase())
+}
+
+///
+/// Formats all six ability scores for a row of the statblock
+///
+fn ability_row(creature: &Creature) -> Vec<String> {
+    vec![
+        ability_string("STR", creature.str),
+        ability_string("DEX", creature.dex),
+        ability_string("CON", creature.con),
+        ability_string("INT", creature.int),
+        ability_string("WIS", creature.wis),
+        ability_string("CHA", creature.cha),
+    ]
+}
+
+///
+/// Loot drops a single item unless the statblock gives a dice expression for the quantity
+///
+fn default_loot_quantity() -> String {
+    "1".to_string()
+}
+
+///
+/// Loot always drops unless the statblock gives a percent chance
+///
+fn default_loot_chance() -> i32 {
+    100
+}
+
+///
+/// Converts an ability's short or full name (e.g. "con" or "Constitution") to its statblock key
+///
+pub fn ability_key(ability: &str) -> Option<&'static str> {
+    let ability = ability.trim().to_lowercase();
+    ABILITIES.iter().zip(ABILITY_NAMES).find(|(key, name)| ability == **key || ability == *name).map(|(key, _)| *key)
+}
+
+///
+/// Gets a creature's raw score for an ability key
+///
+fn ability_score(creature: &Creature, key: &str) -> i32 {
+    match key {
+        "str" => creature.str,
+        "dex" => creature.dex,
+        "con" => creature.con,
+        "int" => creature.int,
+        "wis" => creature.wis,
+        _ => creature.cha,
+    }
+}
+
+///
+/// Computes a creature's saving throw modifier, adding proficiency if the statblock lists the save
+///
+fn save_modifier(creature: &Creature, key: &str) -> i32 {
+    let proficient = creature.saving_throws.iter().any(|save| ability_key(save) == Some(key));
+    ability_modifier(ability_score(creature, key)) + if proficient {creature.proficiency_bonus} else {0}
+}
+
+///
+/// Computes a creature's modifier for a skill or raw ability check, or None if the check doesn't exist
+///
+fn check_modifier(creature: &Creature, check: &str) -> Option<i32> {
+    let check = check.trim().to_lowercase();
+    if let Some((skill, key)) = SKILLS.iter().find(|(skill, _)| *skill == check) {
+        let proficient = creature.skills.iter().any(|s| s.to_lowercase() == *skill);
+        Some(ability_modifier(ability_score(creature, key)) + if proficient {creature.proficiency_bonus} else {0})
     } else {
-        println!("\nCreature not found.\n");
+        ability_key(&check).map(|key| ability_modifier(ability_score(creature, key)))
     }
 }
 
 ///
-/// Parses json data from a given input
+/// Gets the XP a monster is worth, or None if the creature has no statblock
+///
+pub fn monster_xp(creature_stat: &str) -> Option<i32> {
+    let creatures = load_statblocks();
+    creatures.iter().find(|c| c.name.to_lowercase() == creature_stat.to_lowercase()).map(|c| c.xp)
+}
+
+///
+/// Gets the loot table for a monster, which is empty if the creature has no statblock
+///
+pub fn monster_loot(creature_stat: &str) -> Vec<Loot> {
+    let creatures = load_statblocks();
+    creatures.into_iter().find(|c| c.name.to_lowercase() == creature_stat.to_lowercase()).map(|c| c.loot).unwrap_or_default()
+}
+
+///
+/// Checks whether a name is a known skill
+///
+pub fn is_skill(skill: &str) -> bool {
+    let skill = skill.trim().to_lowercase();
+    SKILLS.iter().any(|(name, _)| *name == skill)
+}
+
+///
+/// Checks whether a name is a known skill or ability that can be rolled as a check
+///
+pub fn is_check(check: &str) -> bool {
+    let check = check.trim().to_lowercase();
+    is_skill(&check) || ability_key(&check).is_some()
+}
+
+///
+/// Gets a monster's bonus for a skill or ability check, or None if the creature or check doesn't exist
+///
+pub fn check_bonus(creature_stat: &str, check: &str) -> Option<i32> {
+    let creatures = load_statblocks();
+    creatures.iter().find(|c| c.name.to_lowercase() == creature_stat.to_lowercase()).and_then(|c| check_modifier(c, check))
+}
+
+///
+/// Gets a monster's saving throw bonus for an ability, or None if the creature has no statblock
 ///
-fn parse_json(json_data: &str) -> Result<Vec<Creature>> {
-    let creatures: Vec<Creature> = serde_json::from_str(json_data)?;
-    Ok(creatures)
+pub fn save_bonus(creature_stat: &str, ability: &str) -> Option<i32> {
+    let key = ability_key(ability)?;
+    let creatures = load_statblocks();
+    creatures.iter().find(|c| c.name.to_lowercase() == creature_stat.to_lowercase()).map(|c| save_modifier(c, key))
 }
@@ -0,0 +1,91 @@
+use crate::user_input;
+use crate::encounter;
+use crate::encounter::Character;
+use crate::log;
+use crate::log::EventKind;
+use crate::turn;
+
+///
+/// Marks a creature as surprised, so it loses its next turn, or takes the surprise away
+///
+pub fn set_surprised(characters: &mut [Character], index: usize, surprised: bool) -> String {
+    characters[index].surprised = surprised;
+    let message = if surprised {
+        format!("{}/{} is surprised", characters[index].character_type, characters[index].name)
+    } else {
+        format!("{}/{} is no longer surprised", characters[index].character_type, characters[index].name)
+    };
+    log::record_for(EventKind::Condition, &message, None, &[characters[index].id]);
+    message
+}
+
+///
+/// Hides a creature from the players until it's revealed
+///
+pub fn hide(characters: &mut [Character], index: usize) -> String {
+    characters[index].hidden = true;
+    let message = format!("{}/{} is hidden from the players", characters[index].character_type, characters[index].name);
+    log::record_for(EventKind::Condition, &message, None, &[characters[index].id]);
+    message
+}
+
+///
+/// Holds a creature back until the given round, or brings it in straight away if that round has already come
+///
+pub fn set_joins_round(characters: &mut [Character], index: usize, joins: usize, round: usize) -> String {
+    let label = format!("{}/{}", characters[index].character_type, characters[index].name);
+    let message = if joins > round {
+        characters[index].joins_round = Some(joins);
+        format!("{} will join the fight in round {}", label, joins)
+    } else {
+        characters[index].joins_round = None;
+        format!("{} joins the fight", label)
+    };
+    log::record_for(EventKind::Turn, &message, None, &[characters[index].id]);
+    message
+}
+
+///
+/// Menu used to mark creatures as surprised, hidden from the players, or reinforcements that join in a later round
+///
+pub fn surprise_menu() {
+    loop {
+        let mut characters = encounter::load_encounter_file();
+        let round = turn::load_turn().round;
+        encounter::print_creatures(&characters);
+        println!("Choose or enter the numbers, names or #IDs of the creatures to mark, separated by commas, or press enter to return:");
+        let selected = encounter::creature_list_input(&characters);
+        if selected.is_empty() {
+            break;
+        }
+
+        println!("\nMark them as (s)urprised or (n)ot surprised, (h)ide or (r)eveal them, or have them (j)oin in a later round:");
+        let input = user_input::input();
+        println!();
+        let joins = if input == "j" {
+            println!("It's round {}. Enter the round they join in:", round);
+            let joins = user_input::usize_input();
+            println!();
+            joins
+        } else {
+            0
+        };
+
+        for index in selected {
+            let message = match input.as_str() {
+                "s" | "n" => set_surprised(&mut characters, index, input == "s"),
+                "h" => hide(&mut characters, index),
+                "r" => encounter::reveal(&mut characters, index).unwrap_or(format!("{}/{} wasn't hidden", characters[index].character_type, characters[index].name)),
+                "j" => set_joins_round(&mut characters, index, joins, round),
+                _ => {
+                    println!("Invalid input!");
+                    break;
+                }
+            };
+            println!("{}", message);
+        }
+        encounter::save_encounter_file(&mut characters);
+        println!("\nPress enter to continue:");
+        user_input::input();
+    }
+}
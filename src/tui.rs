@@ -0,0 +1,615 @@
+use crate::encounter;
+use crate::encounter::{Character, Condition};
+use crate::stat_search;
+use crate::checks;
+use crate::conditions;
+use crate::history;
+use crate::turn;
+use crate::turn::TurnState;
+use crate::log;
+use crate::log::EventKind;
+use std::io;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use titlecase::titlecase;
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+
+///
+/// Actions that need a line of text typed into the command bar before they run
+///
+#[derive(Clone, Copy, PartialEq)]
+enum Prompt {
+    Damage,
+    Heal,
+    Condition,
+    AddMonster,
+    ConfirmRemove,
+    Check,
+    Edit(Field),
+}
+
+///
+/// Fields of a creature that can be edited, in the order they're listed
+///
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Race,
+    Ac,
+    Hp,
+    Initiative,
+    Level,
+}
+
+///
+/// Choices picked from a list with the arrow keys, which is shown in place of the statblock
+///
+#[derive(Clone, Copy, PartialEq)]
+enum MenuKind {
+    AttackTarget,
+    AttackAction { target_id: u64 },
+    EditField,
+}
+
+///
+/// Menu struct used for storing an open list of choices and which one is highlighted
+///
+struct Menu {
+    kind: MenuKind,
+    title: String,
+    options: Vec<String>,
+    cursor: usize,
+}
+
+///
+/// App struct used for storing the interface's selection, command bar state and the selected creature's statblock
+/// between frames
+///
+struct App {
+    selected: usize,
+    selected_id: Option<u64>,
+    prompt: Option<Prompt>,
+    menu: Option<Menu>,
+    input: String,
+    message: String,
+    // The statblock only changes when another kind of creature is selected, so it's kept instead of looked up every frame
+    statblock: Option<(String, Vec<String>)>,
+}
+
+///
+/// Text shown in the command bar when nothing is being typed
+///
+const HINTS: &str = "↑/↓ select  n/p turn  d damage  h heal  t attack  c condition  C clear  s check  e edit  a add  r remove  u/y undo/redo  m player view  q quit";
+
+///
+/// Text shown in the command bar while a menu is open
+///
+const MENU_HINTS: &str = "↑/↓ move  enter choose  esc cancel";
+
+///
+/// Text shown in the command bar in the player view, where only the turn can be moved
+///
+const PLAYER_HINTS: &str = "n/p turn  u/y undo/redo  m DM view  q quit";
+
+///
+/// Runs the full-screen interface until the user quits
+///
+pub fn run() -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+///
+/// Redraws the interface and handles key presses, reloading the encounter each frame so outside changes show up
+///
+fn event_loop(terminal: &mut DefaultTerminal) -> io::Result<()> {
+    let mut app = App {
+        selected: 0,
+        selected_id: None,
+        prompt: None,
+        menu: None,
+        input: String::new(),
+        message: if crate::PLAYER_VIEW.load(Ordering::SeqCst) {PLAYER_HINTS} else {HINTS}.to_string(),
+        statblock: None,
+    };
+
+    loop {
+        let characters = encounter::load_encounter_file();
+        let state = turn::load_turn();
+        // Follows the selected creature by its ID, so the selection stays on it when the order changes
+        match app.selected_id.and_then(|id| characters.iter().position(|c| c.id == id)) {
+            Some(index) => app.selected = index,
+            None => app.selected = app.selected.min(characters.len().saturating_sub(1)),
+        }
+        app.selected_id = characters.get(app.selected).map(|c| c.id);
+        if let Some(creature) = characters.get(app.selected).filter(|c| c.character_type != "Player") {
+            if app.statblock.as_ref().is_none_or(|(kind, _)| *kind != creature.character_type) {
+                app.statblock = Some((creature.character_type.clone(), stat_search::statblock_lines(&creature.character_type)));
+            }
+        }
+        terminal.draw(|frame| draw(frame, &app, &characters, &state))?;
+
+        // Polls so the screen refreshes even when no keys are pressed, and redraws on resize
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        if let Some(mut menu) = app.menu.take() {
+            match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {menu.cursor = menu.cursor.saturating_sub(1);},
+                KeyCode::Down | KeyCode::Char('j') => {menu.cursor = std::cmp::min(menu.cursor + 1, menu.options.len().saturating_sub(1));},
+                KeyCode::Enter => {
+                    app.message = choose(terminal, &mut app, &menu)?;
+                    continue;
+                },
+                KeyCode::Esc => {
+                    app.message = HINTS.to_string();
+                    continue;
+                },
+                _ => {}
+            }
+            app.menu = Some(menu);
+            continue;
+        }
+
+        if let Some(prompt) = app.prompt {
+            match key.code {
+                KeyCode::Enter => {
+                    let input = std::mem::take(&mut app.input);
+                    app.prompt = None;
+                    app.message = submit(terminal, prompt, &input, app.selected_id)?;
+                },
+                KeyCode::Esc => {
+                    app.prompt = None;
+                    app.input.clear();
+                    app.message = HINTS.to_string();
+                },
+                KeyCode::Backspace => {app.input.pop();},
+                KeyCode::Char(c) => {app.input.push(c);},
+                _ => {}
+            }
+            continue;
+        }
+
+        // The player view only moves the turn along, since everything else shows or changes the DM's stats
+        if crate::PLAYER_VIEW.load(Ordering::SeqCst) {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('m') => {
+                    crate::PLAYER_VIEW.store(false, Ordering::SeqCst);
+                    app.message = HINTS.to_string();
+                },
+                KeyCode::Char('n') | KeyCode::Char('p') if !characters.is_empty() => {
+                    if let Some((state, _)) = turn::take_turn(key.code == KeyCode::Char('n')) {
+                        let creature = &characters[state.position - 1];
+                        app.message = if encounter::shown_to_players(creature, state.round) {
+                            format!("Round {}: {}'s turn", state.round, creature.name)
+                        } else {
+                            format!("Round {}", state.round)
+                        };
+                    }
+                },
+                KeyCode::Char('u') => {app.message = history::undo().map(|_| "Undid the last change".to_string()).unwrap_or_else(|e| format!("{}!", e));},
+                KeyCode::Char('y') => {app.message = history::redo().map(|_| "Redid the last undone change".to_string()).unwrap_or_else(|e| format!("{}!", e));},
+                _ => {}
+            }
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('m') => {
+                crate::PLAYER_VIEW.store(true, Ordering::SeqCst);
+                app.message = PLAYER_HINTS.to_string();
+            },
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.selected = app.selected.saturating_sub(1);
+                app.selected_id = characters.get(app.selected).map(|c| c.id);
+            },
+            KeyCode::Down | KeyCode::Char('j') if app.selected + 1 < characters.len() => {
+                app.selected += 1;
+                app.selected_id = characters.get(app.selected).map(|c| c.id);
+            },
+            KeyCode::Char('n') | KeyCode::Char('p') if !characters.is_empty() => {
+                if let Some((state, notes)) = turn::take_turn(key.code == KeyCode::Char('n')) {
+                    app.message = notes.join(". ");
+                    app.selected = state.position-1;
+                    app.selected_id = characters.get(app.selected).map(|c| c.id);
+                }
+            },
+            KeyCode::Char('d') if !characters.is_empty() => {app.prompt = Some(Prompt::Damage);},
+            KeyCode::Char('h') if !characters.is_empty() => {app.prompt = Some(Prompt::Heal);},
+            KeyCode::Char('c') if !characters.is_empty() => {app.prompt = Some(Prompt::Condition);},
+            KeyCode::Char('r') if !characters.is_empty() => {app.prompt = Some(Prompt::ConfirmRemove);},
+            KeyCode::Char('a') => {app.prompt = Some(Prompt::AddMonster);},
+            KeyCode::Char('s') if !characters.is_empty() => {app.prompt = Some(Prompt::Check);},
+            KeyCode::Char('t') if !characters.is_empty() => {
+                let attacker = &characters[app.selected];
+                if stat_search::find_statblock(&attacker.character_type).is_none() {
+                    app.message = format!("{} doesn't have a statblock to attack with", conditions::caster_label(attacker));
+                } else {
+                    app.menu = Some(Menu {
+                        kind: MenuKind::AttackTarget,
+                        title: format!(" {} attacks... ", conditions::caster_label(attacker)),
+                        options: characters.iter().map(|c| format!("{} (#{}), AC {}", conditions::caster_label(c), c.id, c.ac)).collect(),
+                        cursor: 0,
+                    });
+                    app.message = MENU_HINTS.to_string();
+                }
+            },
+            KeyCode::Char('e') if !characters.is_empty() => {
+                let creature = &characters[app.selected];
+                let mut options = vec![
+                    format!("Name: {}", creature.name),
+                    format!("Race: {}", creature.character_type),
+                    format!("AC: {}", creature.ac),
+                    format!("HP: {}", creature.hp),
+                    format!("Initiative: {}", creature.initiative),
+                ];
+                if creature.character_type == "Player" {
+                    options.push(format!("Level: {}", creature.level));
+                }
+                app.menu = Some(Menu {
+                    kind: MenuKind::EditField,
+                    title: format!(" Editing {} ", conditions::caster_label(creature)),
+                    options,
+                    cursor: 0,
+                });
+                app.message = MENU_HINTS.to_string();
+            },
+            KeyCode::Char('C') if !characters.is_empty() => {
+                let mut characters = characters;
+                let label = conditions::caster_label(&characters[app.selected]);
+                characters[app.selected].conditions.clear();
+                log::record_for(EventKind::Condition, &format!("Cleared {}'s conditions", label), None, &[characters[app.selected].id]);
+                encounter::save_encounter_file(&mut characters);
+                app.message = format!("Cleared {}'s conditions", label);
+            },
+            KeyCode::Char('u') => {app.message = history::undo().map(|_| "Undid the last change".to_string()).unwrap_or_else(|e| format!("{}!", e));},
+            KeyCode::Char('y') => {app.message = history::redo().map(|_| "Redid the last undone change".to_string()).unwrap_or_else(|e| format!("{}!", e));},
+            _ => {}
+        }
+    }
+}
+
+///
+/// Acts on the option chosen from a menu, either opening the next step or running the action, and returns the
+/// message for the command bar
+///
+fn choose(terminal: &mut DefaultTerminal, app: &mut App, menu: &Menu) -> io::Result<String> {
+    let characters = encounter::load_encounter_file();
+    let Some(selected) = app.selected_id.and_then(|id| characters.iter().position(|c| c.id == id)) else {
+        return Ok("That creature has left the encounter".to_string());
+    };
+    match menu.kind {
+        MenuKind::AttackTarget => {
+            let Some(target) = characters.get(menu.cursor) else {
+                return Ok(HINTS.to_string());
+            };
+            let actions = stat_search::find_statblock(&characters[selected].character_type).map(|statblock| statblock.actions).unwrap_or_default();
+            app.menu = Some(Menu {
+                kind: MenuKind::AttackAction { target_id: target.id },
+                title: format!(" {} attacks {} with... ", conditions::caster_label(&characters[selected]), conditions::caster_label(target)),
+                options: actions.iter().map(|action| action.name.clone()).collect(),
+                cursor: 0,
+            });
+            Ok(MENU_HINTS.to_string())
+        },
+        MenuKind::AttackAction { target_id } => attack(terminal, characters, selected, target_id, menu.cursor),
+        MenuKind::EditField => {
+            let field = [Field::Name, Field::Race, Field::Ac, Field::Hp, Field::Initiative, Field::Level][menu.cursor];
+            app.prompt = Some(Prompt::Edit(field));
+            Ok(HINTS.to_string())
+        },
+    }
+}
+
+///
+/// Rolls a monster's action against a target and applies it. Saving throws that can't be rolled from a statblock ask
+/// for the roll, so the normal terminal is restored while they run
+///
+fn attack(terminal: &mut DefaultTerminal, mut characters: Vec<Character>, attacker: usize, target_id: u64, action: usize) -> io::Result<String> {
+    let Some(target) = characters.iter().position(|c| c.id == target_id) else {
+        return Ok("That creature has left the encounter".to_string());
+    };
+    let Some(action) = stat_search::find_statblock(&characters[attacker].character_type).and_then(|statblock| statblock.actions.get(action).cloned()) else {
+        return Ok(HINTS.to_string());
+    };
+
+    let target_type = &characters[target].character_type;
+    let save_roll = action.save.as_ref().is_some_and(|save| stat_search::save_bonus(target_type, &save.ability).is_none());
+    let concentration_roll = characters[target].concentration.is_some() && stat_search::save_bonus(target_type, "con").is_none();
+    let suspend = save_roll || concentration_roll;
+    if suspend {
+        ratatui::restore();
+    }
+    let (lines, _, _) = encounter::resolve_attack(&mut characters, attacker, target, &action, None, None);
+    if suspend {
+        *terminal = ratatui::init();
+        terminal.clear()?;
+    }
+    encounter::save_encounter_file(&mut characters);
+    Ok(lines.join(". "))
+}
+
+///
+/// Runs the action for a prompt once its input has been entered, returning the message for the command bar.
+/// The selected creature is found by its ID in case the encounter changed while the input was being typed
+///
+fn submit(terminal: &mut DefaultTerminal, prompt: Prompt, input: &str, selected_id: Option<u64>) -> io::Result<String> {
+    let mut characters = encounter::load_encounter_file();
+    let selected = match selected_id.and_then(|id| characters.iter().position(|c| c.id == id)) {
+        Some(index) => index,
+        None if prompt == Prompt::AddMonster => 0,
+        None => return Ok("That creature has left the encounter".to_string()),
+    };
+
+    let message = match prompt {
+        Prompt::Damage | Prompt::Heal => {
+            let amount: i32 = match input.trim().parse() {
+                Ok(amount) => amount,
+                Err(..) => return Ok("Please enter a whole number!".to_string()),
+            };
+            let damage = if prompt == Prompt::Damage {amount} else {-amount};
+
+            // Concentration saves can ask players for their roll, so the normal terminal is restored while they run
+            let suspend = damage > 0 && characters[selected].concentration.is_some();
+            if suspend {
+                ratatui::restore();
+            }
+            let result = encounter::apply_damage(&mut characters, selected, damage, None);
+            if suspend {
+                *terminal = ratatui::init();
+                terminal.clear()?;
+            }
+
+            let mut message = format!("{}/{} is now at {} HP", characters[selected].character_type, characters[selected].name, characters[selected].hp);
+            if let Some(result) = result {
+                message = format!("{}. {}", message, result);
+            }
+            encounter::save_encounter_file(&mut characters);
+            message
+        },
+        Prompt::Condition => {
+            let name = titlecase(input.trim());
+            if name.is_empty() {
+                return Ok(HINTS.to_string());
+            }
+            let label = conditions::caster_label(&characters[selected]);
+            log::record_for(EventKind::Condition, &format!("{} is now {}", label, name), None, &[characters[selected].id]);
+            characters[selected].conditions.push(Condition {
+                name: name.clone(),
+                spell: None,
+                caster: None,
+                caster_id: None,
+            });
+            encounter::save_encounter_file(&mut characters);
+            format!("{} is now {}", label, name)
+        },
+        Prompt::AddMonster => {
+            let base = titlecase(input.trim());
+            let name = encounter::unique_name(&base, &characters, true);
+            match stat_search::create_monster(input.trim(), name) {
+                Some(character) => {
+                    let message = format!("Added {}/{} with initiative {}", character.character_type, character.name, character.initiative);
+                    characters.push(character);
+                    encounter::save_added(&mut characters, std::slice::from_ref(&message));
+                    message
+                },
+                None => match stat_search::suggest_monsters(input.trim()).first() {
+                    Some(suggestion) => format!("Unknown monster: {}. Did you mean {}?", input.trim(), suggestion),
+                    None => format!("Unknown monster: {}", input.trim()),
+                },
+            }
+        },
+        Prompt::ConfirmRemove => {
+            if input.trim().to_lowercase() != "y" {
+                return Ok(HINTS.to_string());
+            }
+            conditions::drop_concentration(&mut characters, selected);
+            let character = characters.remove(selected);
+            let message = format!("Removed {}/{}", character.character_type, character.name);
+            log::record_for(EventKind::Remove, &message, None, &[character.id]);
+            encounter::save_encounter_file(&mut characters);
+            message
+        },
+        Prompt::Check => {
+            let check = input.trim().to_lowercase();
+            if check.is_empty() {
+                return Ok(HINTS.to_string());
+            }
+            if !stat_search::is_check(&check) {
+                return Ok(format!("{} isn't a skill or ability. Enter one like athletics or dex", input.trim()));
+            }
+            // Players are asked for their roll, so the normal terminal is restored while they enter it
+            let suspend = stat_search::check_bonus(&characters[selected].character_type, &check).is_none();
+            if suspend {
+                ratatui::restore();
+            }
+            let (roll_string, _) = checks::roll_check(&characters[selected], &check);
+            if suspend {
+                *terminal = ratatui::init();
+                terminal.clear()?;
+            }
+            let message = format!("{} {} on {}", conditions::caster_label(&characters[selected]), roll_string, titlecase(&check));
+            log::record_for(EventKind::Roll, &message, None, &[characters[selected].id]);
+            message
+        },
+        Prompt::Edit(field) => {
+            let value = input.trim();
+            if value.is_empty() {
+                return Ok(HINTS.to_string());
+            }
+            let number = value.parse::<i32>();
+            let character = &mut characters[selected];
+            match (field, number) {
+                (Field::Name, _) => character.name = titlecase(value),
+                (Field::Race, _) => character.character_type = titlecase(value),
+                (Field::Ac, Ok(number)) => character.ac = number,
+                (Field::Hp, Ok(number)) => character.hp = number,
+                (Field::Initiative, Ok(number)) => character.initiative = number,
+                (Field::Level, Ok(number)) => character.level = number,
+                (_, Err(..)) => return Ok("Please enter a whole number!".to_string()),
+            }
+            let message = format!("Updated {}", conditions::caster_label(character));
+            encounter::save_encounter_file(&mut characters);
+            message
+        },
+    };
+    Ok(message)
+}
+
+///
+/// Builds a line for a creature in the initiative panel
+///
+fn creature_item(creature: &Character, current: bool, round: usize) -> ListItem<'static> {
+    let marker = if current {"▶ "} else {"  "};
+    let mut text = if creature.character_type == "Player" {
+        format!("{}{:>2}  {} (#{}), AC {}", marker, creature.initiative, creature.name, creature.id, creature.ac)
+    } else {
+        format!("{}{:>2}  {}/{} (#{}), AC {}, HP {}", marker, creature.initiative, creature.character_type, creature.name, creature.id, creature.ac, creature.hp)
+    };
+    let mut effects = encounter::status_tags(creature, round);
+    if let Some(spell) = &creature.concentration {
+        effects.push(format!("Concentrating: {}", spell));
+    }
+    effects.extend(creature.conditions.iter().map(|c| c.name.clone()));
+    if !effects.is_empty() {
+        text.push_str(&format!(" [{}]", effects.join(", ")));
+    }
+
+    let color = if creature.character_type == "Player" {Color::LightBlue} else {Color::LightRed};
+    let mut style = Style::default().fg(color);
+    if current {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    ListItem::new(Line::from(Span::styled(text, style)))
+}
+
+///
+/// Builds the player view's line for a creature, showing only its name, how hurt it looks and its conditions
+///
+fn player_item(creature: &Character, current: bool) -> ListItem<'static> {
+    let marker = if current {"▶ "} else {"  "};
+    let mut text = if creature.character_type == "Player" {
+        format!("{}{}", marker, creature.name)
+    } else {
+        format!("{}{} ({})", marker, creature.name, encounter::health_band(creature))
+    };
+    let mut effects: Vec<String> = creature.conditions.iter().map(|c| c.name.clone()).collect();
+    if let Some(spell) = &creature.concentration {
+        effects.insert(0, format!("Concentrating: {}", spell));
+    }
+    if !effects.is_empty() {
+        text.push_str(&format!(" [{}]", effects.join(", ")));
+    }
+
+    let color = if creature.character_type == "Player" {Color::LightBlue} else {Color::LightRed};
+    let mut style = Style::default().fg(color);
+    if current {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    ListItem::new(Line::from(Span::styled(text, style)))
+}
+
+///
+/// Draws the initiative, statblock and log panes along with the command bar, or just the turn order in the player view
+///
+fn draw(frame: &mut Frame, app: &App, characters: &[Character], state: &TurnState) {
+    let [main, bar] = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(frame.area());
+    if crate::PLAYER_VIEW.load(Ordering::SeqCst) {
+        // Hidden creatures and reinforcements that haven't arrived are left out entirely
+        let items: Vec<ListItem> = characters.iter().enumerate()
+            .filter(|(_, creature)| encounter::shown_to_players(creature, state.round))
+            .map(|(index, creature)| player_item(creature, index + 1 == state.position))
+            .collect();
+        let list = List::new(items).block(Block::default().borders(Borders::ALL).title(format!(" Round {} (player view) ", state.round)));
+        frame.render_widget(list, main);
+        let command_bar = Paragraph::new(app.message.clone()).block(Block::default().borders(Borders::ALL).title(" Command "));
+        frame.render_widget(command_bar, bar);
+        return;
+    }
+    let [left, right] = Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(main);
+    let [order, log_area] = Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(left);
+
+    // Initiative order, with the current turn marked and the selected creature highlighted
+    let items: Vec<ListItem> = characters.iter().enumerate().map(|(index, creature)| creature_item(creature, index + 1 == state.position, state.round)).collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!(" Round {} ", state.round)))
+        .highlight_style(Style::default().bg(Color::DarkGray));
+    let mut list_state = ListState::default();
+    if !characters.is_empty() {
+        list_state.select(Some(app.selected));
+    }
+    frame.render_stateful_widget(list, order, &mut list_state);
+
+    // An open menu takes the statblock's place
+    if let Some(menu) = &app.menu {
+        let items: Vec<ListItem> = menu.options.iter().map(|option| ListItem::new(option.clone())).collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(menu.title.clone()))
+            .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+            .highlight_symbol("▶ ");
+        let mut menu_state = ListState::default();
+        menu_state.select(Some(menu.cursor));
+        frame.render_stateful_widget(list, right, &mut menu_state);
+    }
+
+    // Statblock for the selected creature
+    let (title, lines) = match characters.get(app.selected) {
+        Some(creature) if creature.character_type != "Player" => {
+            let lines = app.statblock.as_ref().map(|(_, lines)| lines.clone()).unwrap_or_default();
+            (format!(" {}/{} ", creature.character_type, creature.name), lines)
+        },
+        Some(creature) => (format!(" {} ", creature.name), vec![format!("Player character, AC {}", creature.ac)]),
+        None => (" Statblock ".to_string(), vec!["Press a to add a monster".to_string()]),
+    };
+    let statblock = Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<Line>>())
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .wrap(Wrap { trim: false });
+    if app.menu.is_none() {
+        frame.render_widget(statblock, right);
+    }
+
+    // Most recent log entries that fit in the pane
+    let visible = log_area.height.saturating_sub(2) as usize;
+    let log_lines: Vec<Line> = match log::read_log() {
+        Ok(events) => events.iter().skip(events.len().saturating_sub(visible)).map(|event| Line::from(format!("{:?}: {}", event.kind, event.message))).collect(),
+        Err(e) => vec![Line::from(e)],
+    };
+    let log_pane = Paragraph::new(log_lines).block(Block::default().borders(Borders::ALL).title(" Combat Log "));
+    frame.render_widget(log_pane, log_area);
+
+    // Command bar, which doubles as the input line while a prompt is open
+    let bar_text = match app.prompt {
+        Some(Prompt::Damage) => format!("Damage dealt: {}_", app.input),
+        Some(Prompt::Heal) => format!("HP healed: {}_", app.input),
+        Some(Prompt::Condition) => format!("Condition to apply: {}_", app.input),
+        Some(Prompt::AddMonster) => format!("Monster type to add: {}_", app.input),
+        Some(Prompt::ConfirmRemove) => format!("Remove this creature? (y/n): {}_", app.input),
+        Some(Prompt::Check) => format!("Skill or ability to check: {}_", app.input),
+        Some(Prompt::Edit(field)) => format!("New {}: {}_", match field {
+            Field::Name => "name",
+            Field::Race => "race",
+            Field::Ac => "AC",
+            Field::Hp => "HP",
+            Field::Initiative => "initiative",
+            Field::Level => "level",
+        }, app.input),
+        None => app.message.clone(),
+    };
+    let command_bar = Paragraph::new(bar_text).block(Block::default().borders(Borders::ALL).title(" Command "));
+    frame.render_widget(command_bar, bar);
+}
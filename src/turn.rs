@@ -0,0 +1,400 @@
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::path::PathBuf;
+use crate::history;
+use crate::encounter;
+use crate::encounter::Character;
+use crate::log;
+use crate::log::EventKind;
+
+///
+/// TurnState struct used for persisting the current round and whose turn it is. The turn belongs to the creature
+/// with the current ID, and position is where that creature sits in the initiative order
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TurnState {
+    pub round: usize,
+    pub position: usize,
+    #[serde(default)]
+    pub current: Option<u64>,
+}
+
+impl Default for TurnState {
+    fn default() -> Self {
+        TurnState {
+            round: 1,
+            position: 1,
+            current: None,
+        }
+    }
+}
+
+///
+/// Loads the turn state, starting on the first creature of round 1 if it doesn't exist or warning first if it can't be
+/// read
+///
+pub fn load_turn() -> TurnState {
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker/turn.json").into_owned();
+    let path = PathBuf::from(expanded_path);
+    let state = match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("{} couldn't be read as the turn order ({}), so it starts over from round 1", path.display(), e);
+            TurnState::default()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => TurnState::default(),
+        Err(e) => {
+            eprintln!("{} couldn't be read ({}), so it starts over from round 1", path.display(), e);
+            TurnState::default()
+        }
+    };
+    sync(&state, &encounter::load_encounter_file())
+}
+
+///
+/// Saves the turn state, recording the previous state in the undo history
+///
+pub fn save_turn(state: &TurnState) {
+    history::record(&encounter::load_encounter_file(), state);
+    write_turn(state);
+}
+
+///
+/// Writes the turn state to ~/.config/dnd-encounter-tracker/turn.json without touching the undo history
+///
+pub fn write_turn(state: &TurnState) {
+    let json_state = serde_json::to_string_pretty(state).unwrap();
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker").into_owned();
+    let path = PathBuf::from(expanded_path);
+    std::fs::create_dir_all(&path).expect("Failed to create directory");
+    let expanded_path = shellexpand::tilde("~/.config/dnd-encounter-tracker/turn.json").into_owned();
+    let path = PathBuf::from(expanded_path);
+    std::fs::write(&path, json_state).expect("Unable to write to file");
+}
+
+///
+/// Puts the turn on the creature at the given index
+///
+fn at(round: usize, characters: &[Character], index: usize) -> TurnState {
+    TurnState {
+        round,
+        position: index + 1,
+        current: characters.get(index).map(|character| character.id),
+    }
+}
+
+///
+/// Finds where the current creature sits in the order. A turn that isn't pinned to a creature yet, because combat
+/// hasn't started or it was saved before creatures had IDs, keeps its saved position
+///
+pub fn sync(state: &TurnState, characters: &[Character]) -> TurnState {
+    if characters.is_empty() {
+        return TurnState { round: state.round, ..TurnState::default() };
+    }
+    let Some(current) = state.current else {
+        return TurnState { round: state.round, position: state.position.clamp(1, characters.len()), current: None };
+    };
+    match characters.iter().position(|character| character.id == current) {
+        Some(index) => at(state.round, characters, index),
+        None => at(state.round, characters, state.position.clamp(1, characters.len()) - 1),
+    }
+}
+
+///
+/// Keeps the turn with the same creature when the encounter changes around it. If that creature was removed, the
+/// turn passes to the next one after it that's still there, or to the top of the next round if there isn't one.
+/// Before combat starts the turn stays unpinned at the top of the order
+///
+pub fn follow(state: &TurnState, old: &[Character], new: &[Character]) -> TurnState {
+    if new.is_empty() {
+        return TurnState { round: state.round, ..TurnState::default() };
+    }
+    if state.current.is_none() && state.round == 1 && state.position == 1 {
+        return TurnState::default();
+    }
+    if let Some(index) = state.current.and_then(|id| new.iter().position(|character| character.id == id)) {
+        return at(state.round, new, index);
+    }
+    // Turns saved before creatures had IDs start looking from the creature at their position
+    let skip = if state.current.is_some() { state.position } else { state.position - 1 };
+    for creature in old.iter().skip(skip) {
+        if let Some(index) = new.iter().position(|character| character.id == creature.id) {
+            return at(state.round, new, index);
+        }
+    }
+    at(state.round + 1, new, 0)
+}
+
+///
+/// Checks whether a creature is a reinforcement that hasn't joined the fight yet in the given round
+///
+pub fn waiting(character: &Character, round: usize) -> bool {
+    character.joins_round.is_some_and(|joins| round < joins)
+}
+
+///
+/// Checks whether a monster has been defeated, which takes it out of the turn order. Players at 0 HP keep their
+/// turns for death saves
+///
+pub fn defeated(character: &Character) -> bool {
+    character.character_type != "Player" && character.hp <= 0
+}
+
+///
+/// Moves to the next creature in order whether or not it can act, noting any reinforcements that join as a new round starts
+///
+fn step(state: &TurnState, characters: &[Character], notes: &mut Vec<String>) -> TurnState {
+    if state.position < characters.len() {
+        return at(state.round, characters, state.position);
+    }
+    let state = at(state.round + 1, characters, 0);
+    for character in characters.iter().filter(|c| c.joins_round == Some(state.round)) {
+        notes.push(format!("{}/{} joins the fight", character.character_type, character.name));
+    }
+    state
+}
+
+///
+/// Moves forward past creatures that can't act: defeated monsters, reinforcements that haven't arrived and surprised
+/// creatures, who lose the turn they're skipped on
+///
+fn skip_unready(state: &TurnState, characters: &mut [Character], notes: &mut Vec<String>) -> TurnState {
+    let mut state = state.clone();
+    // Stops on anyone if nobody will ever be able to act
+    if characters.iter().all(defeated) {
+        return state;
+    }
+    loop {
+        let character = &mut characters[state.position - 1];
+        if defeated(character) || waiting(character, state.round) {
+            state = step(&state, characters, notes);
+        } else if character.surprised {
+            character.surprised = false;
+            notes.push(format!("{}/{} is surprised and loses their turn", character.character_type, character.name));
+            state = step(&state, characters, notes);
+        } else {
+            return state;
+        }
+    }
+}
+
+///
+/// Makes sure the turn starts on a creature that can act, skipping forward past any that can't.
+/// Returns where the turn lands and a note for each change
+///
+pub fn settle(state: &TurnState, characters: &mut [Character]) -> (TurnState, Vec<String>) {
+    let mut notes = Vec::new();
+    if characters.is_empty() {
+        return (sync(state, characters), notes);
+    }
+    let state = skip_unready(&sync(state, characters), characters, &mut notes);
+    (at(state.round, characters, state.position - 1), notes)
+}
+
+///
+/// Moves to the next creature that can act, starting a new round after the last one.
+/// Returns where the turn lands and a note for each change
+///
+pub fn advance(state: &TurnState, characters: &mut [Character]) -> (TurnState, Vec<String>) {
+    let mut notes = Vec::new();
+    if characters.is_empty() {
+        return (sync(state, characters), notes);
+    }
+    let state = step(&sync(state, characters), characters, &mut notes);
+    (skip_unready(&state, characters, &mut notes), notes)
+}
+
+///
+/// Moves back to the previous creature that could act, going back into the previous round from the first one.
+/// Stays put on the first turn of the fight
+///
+pub fn retreat(state: &TurnState, characters: &[Character]) -> TurnState {
+    let current = sync(state, characters);
+    if characters.is_empty() {
+        return current;
+    }
+    let mut state = current.clone();
+    loop {
+        state = if state.position > 1 {
+            at(state.round, characters, state.position - 2)
+        } else if state.round > 1 {
+            at(state.round - 1, characters, characters.len() - 1)
+        } else {
+            return current;
+        };
+        let character = &characters[state.position - 1];
+        if !defeated(character) && !waiting(character, state.round) {
+            return state;
+        }
+    }
+}
+
+///
+/// Moves the turn forwards or backwards and saves it, along with any reinforcements joining or surprise wearing off,
+/// as a single undo step. Returns the new state and what happened, ending with whose turn it is
+///
+pub fn take_turn(forward: bool) -> Option<(TurnState, Vec<String>)> {
+    let mut characters = encounter::load_encounter_file();
+    if characters.is_empty() {
+        return None;
+    }
+    let state = load_turn();
+    let (state, mut notes) = if forward {
+        advance(&state, &mut characters)
+    } else {
+        (retreat(&state, &characters), Vec::new())
+    };
+    history::record(&characters, &state);
+    encounter::write_encounter_file(&characters);
+    write_turn(&state);
+    for note in &notes {
+        log::record(EventKind::Turn, note, None);
+    }
+
+    let creature = &characters[state.position - 1];
+    let message = format!("Round {}: {}/{}'s turn", state.round, creature.character_type, creature.name);
+    log::record_for(EventKind::Turn, &message, None, &[creature.id]);
+    notes.push(message);
+    Some((state, notes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn creature(id: u64, character_type: &str) -> Character {
+        Character {
+            id,
+            name: format!("Creature {}", id),
+            character_type: character_type.to_string(),
+            ac: 12,
+            hp: 10,
+            max_hp: 10,
+            initiative: 10,
+            level: 0,
+            concentration: None,
+            conditions: Vec::new(),
+            surprised: false,
+            hidden: false,
+            joins_round: None,
+        }
+    }
+
+    fn monsters(ids: &[u64]) -> Vec<Character> {
+        ids.iter().map(|&id| creature(id, "Orc")).collect()
+    }
+
+    fn pick(characters: &[Character], ids: &[u64]) -> Vec<Character> {
+        ids.iter().map(|id| characters.iter().find(|c| c.id == *id).unwrap().clone()).collect()
+    }
+
+    #[test]
+    fn follow_keeps_turn_when_earlier_creature_is_removed() {
+        let old = monsters(&[1, 2, 3]);
+        let new = pick(&old, &[2, 3]);
+        let state = follow(&at(2, &old, 1), &old, &new);
+        assert_eq!(state, TurnState { round: 2, position: 1, current: Some(2) });
+    }
+
+    #[test]
+    fn follow_passes_turn_on_when_current_creature_is_removed() {
+        let old = monsters(&[1, 2, 3]);
+        let new = pick(&old, &[1, 3]);
+        let state = follow(&at(2, &old, 1), &old, &new);
+        assert_eq!(state, TurnState { round: 2, position: 2, current: Some(3) });
+    }
+
+    #[test]
+    fn follow_starts_next_round_when_last_creature_is_removed() {
+        let old = monsters(&[1, 2, 3]);
+        let new = pick(&old, &[1, 2]);
+        let state = follow(&at(2, &old, 2), &old, &new);
+        assert_eq!(state, TurnState { round: 3, position: 1, current: Some(1) });
+    }
+
+    #[test]
+    fn follow_keeps_turn_through_a_re_sort() {
+        let old = monsters(&[1, 2, 3]);
+        let new = pick(&old, &[3, 1, 2]);
+        let state = follow(&at(1, &old, 1), &old, &new);
+        assert_eq!(state, TurnState { round: 1, position: 3, current: Some(2) });
+    }
+
+    #[test]
+    fn follow_keeps_first_turn_when_creature_is_added_above() {
+        let old = monsters(&[1, 2]);
+        let new = monsters(&[3, 1, 2]);
+        let state = follow(&at(1, &old, 0), &old, &new);
+        assert_eq!(state, TurnState { round: 1, position: 2, current: Some(1) });
+    }
+
+    #[test]
+    fn follow_stays_at_top_before_combat() {
+        let old = monsters(&[1, 2]);
+        let new = monsters(&[3, 1, 2]);
+        assert_eq!(follow(&TurnState::default(), &old, &new), TurnState::default());
+    }
+
+    #[test]
+    fn follow_uses_position_for_turns_saved_without_ids() {
+        let old = monsters(&[1, 2, 3]);
+        let new = pick(&old, &[1, 3]);
+        let state = follow(&TurnState { round: 2, position: 2, current: None }, &old, &new);
+        assert_eq!(state, TurnState { round: 2, position: 2, current: Some(3) });
+    }
+
+    #[test]
+    fn advance_skips_defeated_monsters() {
+        let mut characters = monsters(&[1, 2, 3]);
+        characters[1].hp = 0;
+        let (state, _) = advance(&at(1, &characters, 0), &mut characters);
+        assert_eq!(state, TurnState { round: 1, position: 3, current: Some(3) });
+    }
+
+    #[test]
+    fn advance_stops_when_everyone_is_defeated() {
+        let mut characters = monsters(&[1, 2]);
+        for character in &mut characters {
+            character.hp = 0;
+        }
+        let (state, notes) = advance(&at(1, &characters, 0), &mut characters);
+        assert_eq!(state, TurnState { round: 1, position: 2, current: Some(2) });
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn advance_brings_in_reinforcements_on_their_round() {
+        let mut characters = monsters(&[1, 2, 3]);
+        characters[2].joins_round = Some(2);
+        let (state, notes) = advance(&at(1, &characters, 1), &mut characters);
+        assert_eq!(state, TurnState { round: 2, position: 1, current: Some(1) });
+        assert_eq!(notes, vec!["Orc/Creature 3 joins the fight".to_string()]);
+
+        let (state, _) = advance(&at(2, &characters, 1), &mut characters);
+        assert_eq!(state, TurnState { round: 2, position: 3, current: Some(3) });
+    }
+
+    #[test]
+    fn settle_skips_surprised_creatures_once() {
+        let mut characters = monsters(&[1, 2]);
+        characters[0].surprised = true;
+        let (state, notes) = settle(&TurnState::default(), &mut characters);
+        assert_eq!(state, TurnState { round: 1, position: 2, current: Some(2) });
+        assert_eq!(notes.len(), 1);
+        assert!(!characters[0].surprised);
+    }
+
+    #[test]
+    fn retreat_goes_back_into_previous_round_past_waiting_reinforcements() {
+        let mut characters = monsters(&[1, 2, 3]);
+        characters[2].joins_round = Some(2);
+        let state = retreat(&at(2, &characters, 0), &characters);
+        assert_eq!(state, TurnState { round: 1, position: 2, current: Some(2) });
+    }
+
+    #[test]
+    fn retreat_stays_on_first_turn_of_the_fight() {
+        let characters = monsters(&[1, 2]);
+        let state = at(1, &characters, 0);
+        assert_eq!(retreat(&state, &characters), state);
+    }
+}
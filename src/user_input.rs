@@ -1,27 +1,134 @@
-use std::io::Stdin;
-use std::io::stdin;
+use crate::encounter;
+use crate::stat_search;
+use std::cell::RefCell;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::exit;
+use colored::*;
+use ratatui::crossterm::{cursor, queue, terminal};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::style::Print;
+use rustyline::{Context, Editor, Helper};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+
+///
+/// Main menu command letters, offered by tab completion alongside creature names
+///
+const COMMANDS: [&str; 27] = ["a", "b", "c", "d", "done", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "quit", "r", "s", "t", "u", "v", "w", "x", "y", "z"];
+
+///
+/// InputHelper struct used for tab completing monster names, creature names and commands
+///
+struct InputHelper;
+
+impl Completer for InputHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        // Completes the whole line, since monster and creature names can contain spaces
+        let typed = line[..pos].to_lowercase();
+        let mut names: Vec<String> = stat_search::monster_names();
+        names.extend(encounter::load_encounter_file().into_iter().map(|c| c.name));
+        names.extend(COMMANDS.iter().map(|c| c.to_string()));
+        names.sort();
+        names.dedup();
+
+        let candidates = names.into_iter()
+            .filter(|name| name.to_lowercase().starts_with(&typed))
+            .map(|name| Pair { display: name.clone(), replacement: name })
+            .collect();
+        Ok((0, candidates))
+    }
+}
+
+impl Hinter for InputHelper {
+    type Hint = String;
+}
+
+impl Highlighter for InputHelper {}
+
+impl Validator for InputHelper {}
+
+impl Helper for InputHelper {}
+
+thread_local! {
+    // Shared line editor, so history carries over between prompts
+    static EDITOR: RefCell<Option<Editor<InputHelper, FileHistory>>> = const { RefCell::new(None) };
+}
+
+///
+/// Path of the file that keeps input history between sessions
+///
+fn history_path() -> PathBuf {
+    PathBuf::from(shellexpand::tilde("~/.config/dnd-encounter-tracker/input_history.txt").into_owned())
+}
+
+///
+/// Reads a line with history and tab completion, exiting cleanly on Ctrl-D, Ctrl-C or the end of input
+///
+fn read_line() -> String {
+    let line = EDITOR.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        if cell.is_none() {
+            let mut editor = Editor::new().expect("Couldn't start the line editor");
+            editor.set_helper(Some(InputHelper));
+            let _ = editor.load_history(&history_path());
+            *cell = Some(editor);
+        }
+        let editor = cell.as_mut().unwrap();
+
+        let line = editor.readline("");
+        if let Ok(line) = &line {
+            if !line.trim().is_empty() {
+                let _ = editor.add_history_entry(line.trim());
+                let _ = editor.save_history(&history_path());
+            }
+        }
+        line
+    });
+
+    match line {
+        Ok(line) => line,
+        Err(ReadlineError::Eof) | Err(ReadlineError::Interrupted) => {
+            println!("\nI hope you enjoyed using this program!");
+            exit(0);
+        },
+        Err(e) => {
+            println!("\nCouldn't read input: {}", e);
+            exit(1);
+        },
+    }
+}
 
 ///
 /// Simple function just to grab user input and convert it to a standard format used throughout the code
 ///
 pub fn input() -> String {
-    let mut input: String = String::new();
-    let stdin: Stdin = stdin();
-    stdin.read_line(&mut input).unwrap();
-    input.trim().to_ascii_lowercase()
+    read_line().trim().to_ascii_lowercase()
 }
 
 
+///
+/// Simple function to grab user input without changing its case, used for file paths
+///
+pub fn raw_input() -> String {
+    read_line().trim().to_string()
+}
+
 ///
 /// Simple function to get a usize from the user
 ///
 pub fn usize_input() -> usize {
-    let mut input: String = String::new();
-    let stdin: Stdin = stdin();
-    stdin.read_line(&mut input).unwrap();
-    match input.trim().parse::<usize>() {
-        Ok(i) => i,
-        Err(..) => { println!("\nPlease enter a whole number:"); usize_input()},
+    loop {
+        match read_line().trim().parse::<usize>() {
+            Ok(i) => return i,
+            Err(..) => println!("\nPlease enter a whole number:"),
+        }
     }
 }
 
@@ -29,11 +136,118 @@ pub fn usize_input() -> usize {
 /// Simple function to get an i32 from the user
 ///
 pub fn int_input() -> i32 {
-    let mut input: String = String::new();
-    let stdin: Stdin = stdin();
-    stdin.read_line(&mut input).unwrap();
-    match input.trim().parse::<i32>() {
-        Ok(i) => i,
-        Err(..) => { println!("\nPlease enter a valid integer:"); int_input()},
+    loop {
+        match read_line().trim().parse::<i32>() {
+            Ok(i) => return i,
+            Err(..) => println!("\nPlease enter a valid integer:"),
+        }
+    }
+}
+
+///
+/// Checks whether a list of options can be picked from with the arrow keys, which needs a terminal tall enough to
+/// show the whole list. Otherwise the options have to be typed, as they are when input is piped in
+///
+pub fn can_pick(count: usize) -> bool {
+    let fits = terminal::size().map(|(_, rows)| count + 2 < rows as usize).unwrap_or(false);
+    count > 0 && fits && std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
+}
+
+///
+/// What the user did with a picker: chose options from the list, typed something else instead, or backed out
+///
+pub enum Picked {
+    Chosen(Vec<usize>),
+    Typed(String),
+    Cancelled,
+}
+
+///
+/// Draws the picker's options, marking the one under the cursor and, when picking several, the ones chosen so far,
+/// followed by whatever has been typed
+///
+fn draw_options(options: &[String], selected: usize, chosen: Option<&[bool]>, typed: &str) {
+    let mut out = std::io::stdout();
+    for (index, option) in options.iter().enumerate() {
+        let check = match chosen {
+            Some(chosen) if chosen[index] => "[x] ",
+            Some(..) => "[ ] ",
+            None => "",
+        };
+        let line = if index == selected && typed.is_empty() {
+            format!("{} {}{}", ">".bright_yellow(), check, option).bold().to_string()
+        } else {
+            format!("  {}{}", check, option)
+        };
+        let _ = queue!(out, terminal::Clear(terminal::ClearType::CurrentLine), Print(line), Print("\r\n"));
+    }
+    let _ = queue!(out, terminal::Clear(terminal::ClearType::CurrentLine), Print(format!("{} {}", "Typed:".italic(), typed)), Print("\r\n"));
+    let _ = out.flush();
+}
+
+///
+/// Lets the user move through the options with the arrow keys, choosing one with enter, or several with space and
+/// then enter. Anything typed is returned as is when enter is pressed, so numbers, names and IDs still work
+///
+fn run_picker(options: &[String], multiple: bool) -> Picked {
+    let hint = if multiple {
+        "↑/↓ to move, space to select, enter to confirm, esc to return, or type numbers, names or #IDs"
+    } else {
+        "↑/↓ to move, enter to choose, esc to return, or type a number, name or #ID"
+    };
+    println!("{}", hint.italic());
+    if terminal::enable_raw_mode().is_err() {
+        return Picked::Typed(input());
     }
+    let mut selected = 0;
+    let mut chosen = vec![false; options.len()];
+    let mut typed = String::new();
+
+    let result = loop {
+        draw_options(options, selected, if multiple {Some(&chosen)} else {None}, &typed);
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+            Ok(..) => {
+                let _ = queue!(std::io::stdout(), cursor::MoveUp(options.len() as u16 + 1));
+                continue;
+            },
+            Err(..) => break Picked::Cancelled,
+        };
+        match key.code {
+            KeyCode::Char('c') | KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let _ = terminal::disable_raw_mode();
+                println!("\nI hope you enjoyed using this program!");
+                exit(0);
+            },
+            KeyCode::Up => selected = selected.saturating_sub(1),
+            KeyCode::Down => selected = std::cmp::min(selected + 1, options.len() - 1),
+            KeyCode::Char(' ') if multiple && typed.is_empty() => chosen[selected] = !chosen[selected],
+            KeyCode::Char(character) => typed.push(character),
+            KeyCode::Backspace => {typed.pop();},
+            KeyCode::Enter if !typed.trim().is_empty() => break Picked::Typed(typed.trim().to_ascii_lowercase()),
+            KeyCode::Enter if multiple => break Picked::Chosen((0..options.len()).filter(|&index| chosen[index]).collect()),
+            KeyCode::Enter => break Picked::Chosen(vec![selected]),
+            KeyCode::Esc => break Picked::Cancelled,
+            _ => {}
+        }
+        let _ = queue!(std::io::stdout(), cursor::MoveUp(options.len() as u16 + 1));
+    };
+    let _ = terminal::disable_raw_mode();
+    println!();
+    result
+}
+
+///
+/// Picks one option with the arrow keys, or takes what the user typed instead. Check [can_pick] first
+///
+pub fn pick(options: &[String]) -> Picked {
+    run_picker(options, false)
+}
+
+///
+/// Picks any number of options with the arrow keys and space, or takes what the user typed instead. Check [can_pick]
+/// first
+///
+pub fn pick_many(options: &[String]) -> Picked {
+    run_picker(options, true)
 }
@@ -0,0 +1,276 @@
+use crate::dice;
+use crate::stat_search;
+use crate::stat_search::Creature;
+use std::fmt;
+use std::fs;
+
+///
+/// Problem struct used for reporting a single invalid value, with the file, creature and field it was found in
+///
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub file: String,
+    pub creature: String,
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}: {}: {}", self.file, self.creature, self.message)
+        } else {
+            write!(f, "{}: {}.{}: {}", self.file, self.creature, self.field, self.message)
+        }
+    }
+}
+
+///
+/// Checks a parsed creature for values that would break the tracker, returning each bad field and what's wrong with it
+///
+pub fn validate_creature(creature: &Creature) -> Vec<(String, String)> {
+    let mut problems = Vec::new();
+    let mut problem = |field: &str, message: String| problems.push((field.to_string(), message));
+
+    if creature.name.trim().is_empty() {
+        problem("name", "name can't be empty".to_string());
+    }
+    if creature.health <= 0 {
+        problem("health", format!("HP must be positive, got {}", creature.health));
+    }
+    if creature.armor_class <= 0 {
+        problem("armor_class", format!("AC must be positive, got {}", creature.armor_class));
+    }
+    if creature.movement_speed < 0 {
+        problem("movement_speed", format!("speed can't be negative, got {}", creature.movement_speed));
+    }
+    if !creature.challenge_rating.is_empty() && stat_search::challenge_value(&creature.challenge_rating).is_none() {
+        problem("challenge_rating", format!("\"{}\" isn't a challenge rating", creature.challenge_rating));
+    }
+    if creature.xp < 0 {
+        problem("xp", format!("XP can't be negative, got {}", creature.xp));
+    }
+    let scores = [creature.str, creature.dex, creature.con, creature.int, creature.wis, creature.cha];
+    for (ability, score) in stat_search::ABILITIES.iter().zip(scores) {
+        if !(1..=30).contains(&score) {
+            problem(ability, format!("ability scores must be from 1 to 30, got {}", score));
+        }
+    }
+    for (index, save) in creature.saving_throws.iter().enumerate() {
+        if stat_search::ability_key(save).is_none() {
+            problem(&format!("saving_throws[{}]", index), format!("\"{}\" isn't an ability", save));
+        }
+    }
+    for (index, skill) in creature.skills.iter().enumerate() {
+        if !stat_search::is_skill(skill) {
+            problem(&format!("skills[{}]", index), format!("\"{}\" isn't a skill", skill));
+        }
+    }
+
+    if creature.actions.is_empty() {
+        problem("actions", "needs at least one action".to_string());
+    }
+    for (index, action) in creature.actions.iter().enumerate() {
+        let field = format!("actions[{}]", index);
+        if action.name.trim().is_empty() {
+            problem(&format!("{}.name", field), "action name can't be empty".to_string());
+        }
+        match action.damage_dice.as_slice() {
+            [count, sides] => {
+                if *count <= 0 {
+                    problem(&format!("{}.damage_dice", field), format!("dice count must be at least 1, got {}", count));
+                }
+                if *sides <= 0 {
+                    problem(&format!("{}.damage_dice", field), format!("dice sides must be at least 1, got {}", sides));
+                }
+            },
+            dice => problem(&format!("{}.damage_dice", field), format!("needs a dice count and number of sides, like [2, 6], got {} values", dice.len())),
+        }
+        if action.damage_type.trim().is_empty() {
+            problem(&format!("{}.damage_type", field), "damage type can't be empty".to_string());
+        }
+        if let Some(save) = &action.save {
+            if stat_search::ability_key(&save.ability).is_none() {
+                problem(&format!("{}.save.ability", field), format!("\"{}\" isn't an ability", save.ability));
+            }
+            if save.dc <= 0 {
+                problem(&format!("{}.save.dc", field), format!("DC must be positive, got {}", save.dc));
+            }
+        }
+    }
+
+    for (index, item) in creature.loot.iter().enumerate() {
+        if dice::parse(&item.quantity).is_none() {
+            problem(&format!("loot[{}].quantity", index), format!("\"{}\" isn't a number or dice expression", item.quantity));
+        }
+        if !(0..=100).contains(&item.chance) {
+            problem(&format!("loot[{}].chance", index), format!("chance must be a percent from 0 to 100, got {}", item.chance));
+        }
+    }
+    problems
+}
+
+///
+/// Loads a statblock file, keeping the valid creatures and reporting a problem for each invalid one.
+/// Returns None if the file doesn't exist
+///
+pub fn check_file(path: &str) -> Option<(Vec<Creature>, Vec<Problem>)> {
+    let contents = fs::read_to_string(path).ok()?;
+    let mut creatures = Vec::new();
+    let mut problems = Vec::new();
+
+    // Parses each entry on its own so one bad creature doesn't stop the rest from loading
+    let entries: Vec<serde_json::Value> = match serde_json::from_str(&contents) {
+        Ok(entries) => entries,
+        Err(e) => {
+            problems.push(Problem { file: path.to_string(), creature: "file".to_string(), field: String::new(), message: format!("couldn't be read as a list of creatures ({})", e) });
+            return Some((creatures, problems));
+        }
+    };
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        let label = match entry.get("name").and_then(|name| name.as_str()) {
+            Some(name) => format!("[{}] {}", index, name),
+            None => format!("[{}]", index),
+        };
+        let creature: Creature = match serde_json::from_value(entry) {
+            Ok(creature) => creature,
+            Err(e) => {
+                problems.push(Problem { file: path.to_string(), creature: label, field: String::new(), message: e.to_string() });
+                continue;
+            }
+        };
+
+        let mut entry_problems: Vec<Problem> = validate_creature(&creature).into_iter()
+            .map(|(field, message)| Problem { file: path.to_string(), creature: label.clone(), field, message })
+            .collect();
+        if creatures.iter().any(|c: &Creature| c.name.to_lowercase() == creature.name.to_lowercase()) {
+            entry_problems.push(Problem { file: path.to_string(), creature: label.clone(), field: "name".to_string(), message: format!("{} is already defined earlier in the file", creature.name) });
+        }
+        if entry_problems.is_empty() {
+            creatures.push(creature);
+        } else {
+            problems.extend(entry_problems);
+        }
+    }
+    Some((creatures, problems))
+}
+
+///
+/// Validates the built-in statblock file and the homebrew file, returning every problem found
+///
+pub fn check_library() -> Vec<Problem> {
+    let mut problems = Vec::new();
+    match check_file(stat_search::STATBLOCK_PATH) {
+        Some((_, file_problems)) => problems.extend(file_problems),
+        None => problems.push(Problem { file: stat_search::STATBLOCK_PATH.to_string(), creature: "file".to_string(), field: String::new(), message: "couldn't be read".to_string() }),
+    }
+    if let Some((_, file_problems)) = check_file(&stat_search::homebrew_path().to_string_lossy()) {
+        problems.extend(file_problems);
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn orc() -> Creature {
+        serde_json::from_value(json!({
+            "name": "Orc",
+            "health": 15,
+            "armor_class": 13,
+            "initiative": 1,
+            "movement_speed": 30,
+            "challenge_rating": "1/2",
+            "xp": 100,
+            "str": 16, "dex": 12, "con": 16, "int": 7, "wis": 11, "cha": 10,
+            "saving_throws": ["con", "Wisdom"],
+            "skills": ["intimidation"],
+            "actions": [{"name": "Greataxe", "description": "", "attack_modifier": 5, "damage_dice": [1, 12], "damage_bonus": 3, "damage_type": "slashing"}],
+            "abilities": [],
+            "loot": [{"name": "Gold", "quantity": "2d6", "chance": 50}]
+        })).unwrap()
+    }
+
+    fn fields(creature: &Creature) -> Vec<String> {
+        validate_creature(creature).into_iter().map(|(field, _)| field).collect()
+    }
+
+    #[test]
+    fn accepts_a_valid_creature() {
+        assert!(validate_creature(&orc()).is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_core_stats() {
+        let mut creature = orc();
+        creature.name = " ".to_string();
+        creature.health = 0;
+        creature.armor_class = -1;
+        creature.movement_speed = -5;
+        creature.xp = -10;
+        assert_eq!(fields(&creature), vec!["name", "health", "armor_class", "movement_speed", "xp"]);
+    }
+
+    #[test]
+    fn rejects_unknown_challenge_ratings() {
+        let mut creature = orc();
+        creature.challenge_rating = "hard".to_string();
+        assert_eq!(fields(&creature), vec!["challenge_rating"]);
+        creature.challenge_rating = String::new();
+        assert!(fields(&creature).is_empty());
+    }
+
+    #[test]
+    fn rejects_ability_scores_out_of_range() {
+        let mut creature = orc();
+        creature.dex = 0;
+        creature.cha = 31;
+        assert_eq!(fields(&creature), vec!["dex", "cha"]);
+    }
+
+    #[test]
+    fn rejects_unknown_saves_and_skills() {
+        let mut creature = orc();
+        creature.saving_throws.push("strawberry".to_string());
+        creature.skills.push("juggling".to_string());
+        assert_eq!(fields(&creature), vec!["saving_throws[2]", "skills[1]"]);
+    }
+
+    #[test]
+    fn rejects_creatures_without_actions() {
+        let mut creature = orc();
+        creature.actions.clear();
+        assert_eq!(fields(&creature), vec!["actions"]);
+    }
+
+    #[test]
+    fn rejects_bad_actions() {
+        let mut creature = orc();
+        creature.actions[0].damage_dice = vec![0, 6];
+        creature.actions[0].damage_type = String::new();
+        creature.actions[0].save = Some(stat_search::ActionSave { ability: "luck".to_string(), dc: 0, half_on_success: false });
+        assert_eq!(fields(&creature), vec!["actions[0].damage_dice", "actions[0].damage_type", "actions[0].save.ability", "actions[0].save.dc"]);
+        creature.actions[0].damage_dice = vec![6];
+        assert!(fields(&creature).contains(&"actions[0].damage_dice".to_string()));
+    }
+
+    #[test]
+    fn rejects_bad_loot() {
+        let mut creature = orc();
+        creature.loot[0].quantity = "a handful".to_string();
+        creature.loot[0].chance = 101;
+        assert_eq!(fields(&creature), vec!["loot[0].quantity", "loot[0].chance"]);
+    }
+
+    #[test]
+    fn accepts_flat_loot_quantities_and_rejects_oversized_dice() {
+        let mut creature = orc();
+        creature.loot[0].quantity = "3".to_string();
+        assert!(fields(&creature).is_empty());
+        creature.loot[0].quantity = format!("{}d6", dice::MAX_DICE + 1);
+        assert_eq!(fields(&creature), vec!["loot[0].quantity"]);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::user_input;
+use crate::encounter;
+use crate::encounter::Character;
+use crate::turn;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+///
+/// How much the dashboard reveals about monster HP
+///
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HpMode {
+    Hidden,
+    Bands,
+}
+
+///
+/// Port the dashboard listens on unless another is given
+///
+pub const DEFAULT_PORT: u16 = 8080;
+
+///
+/// Set once the dashboard is running in the background, so it isn't started twice
+///
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+///
+/// Escapes text for use inside HTML
+///
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+///
+/// Renders a row of the player-facing initiative list. Monsters only show their name and, optionally, a health band
+///
+fn creature_row(creature: &Character, current: bool, hp_mode: HpMode) -> String {
+    let kind = if creature.character_type == "Player" {"player"} else {"monster"};
+    let mut details = Vec::new();
+    if kind == "monster" && hp_mode == HpMode::Bands {
+        let band = encounter::health_band(creature);
+        details.push(format!("<span class=\"band {}\">{}</span>", band.to_lowercase(), band));
+    }
+    for condition in &creature.conditions {
+        details.push(format!("<span class=\"condition\">{}</span>", escape_html(&condition.name)));
+    }
+    if let Some(spell) = &creature.concentration {
+        details.push(format!("<span class=\"condition\">Concentrating: {}</span>", escape_html(spell)));
+    }
+    format!("<li class=\"{}{}\"><span class=\"name\">{}</span>{}</li>\n", kind, if current {" current"} else {""}, escape_html(&creature.name), details.join(""))
+}
+
+///
+/// Renders the round and initiative list, which the page swaps in every few seconds
+///
+fn initiative_fragment(hp_mode: HpMode) -> String {
+    let characters = encounter::load_encounter_file();
+    let state = turn::load_turn();
+    let mut fragment = format!("<h1>Round {}</h1>\n<ol>\n", state.round);
+    for (index, creature) in characters.iter().enumerate().filter(|(_, creature)| encounter::shown_to_players(creature, state.round)) {
+        fragment.push_str(&creature_row(creature, index + 1 == state.position, hp_mode));
+    }
+    fragment.push_str("</ol>\n");
+    if characters.is_empty() {
+        fragment.push_str("<p>Waiting for the encounter to start...</p>\n");
+    }
+    fragment
+}
+
+///
+/// Renders the full page, which polls for the latest initiative order so it follows turns as they advance
+///
+fn page(hp_mode: HpMode) -> String {
+    format!("<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>Initiative</title>
+<style>
+body {{ background: #16161d; color: #eee; font-family: sans-serif; font-size: 2em; margin: 1em 2em; }}
+h1 {{ color: #e8b04a; margin: 0 0 0.5em; }}
+ol {{ list-style: none; padding: 0; margin: 0; }}
+li {{ padding: 0.3em 0.6em; margin: 0.2em 0; border-left: 0.3em solid transparent; border-radius: 0.2em; }}
+li.player .name {{ color: #8ec5ff; }}
+li.monster .name {{ color: #ff8e8e; }}
+li.current {{ background: #2d2d3a; border-left-color: #e8b04a; font-weight: bold; }}
+.band, .condition {{ font-size: 0.6em; margin-left: 0.8em; padding: 0.1em 0.5em; border-radius: 0.3em; background: #333; }}
+.band.unhurt {{ background: #2e6b34; }}
+.band.injured {{ background: #8a7a1f; }}
+.band.bloodied {{ background: #9c4a1a; }}
+.band.critical {{ background: #9c1a1a; }}
+.band.down {{ background: #555; }}
+.condition {{ background: #4b3a6b; }}
+</style>
+</head>
+<body>
+<div id=\"initiative\">
+{}</div>
+<script>
+setInterval(function () {{
+    fetch('/initiative').then(function (response) {{ return response.text(); }}).then(function (html) {{
+        document.getElementById('initiative').innerHTML = html;
+    }}).catch(function () {{}});
+}}, 2000);
+</script>
+</body>
+</html>
+", initiative_fragment(hp_mode))
+}
+
+///
+/// Answers a single HTTP request. Only GET is supported, since the dashboard is read-only
+///
+fn handle_connection(stream: TcpStream, hp_mode: HpMode) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Skips the headers, which the dashboard doesn't need
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/")) => ("200 OK", "text/html; charset=utf-8", page(hp_mode)),
+        (Some("GET"), Some("/initiative")) => ("200 OK", "text/html; charset=utf-8", initiative_fragment(hp_mode)),
+        (Some("GET"), _) => ("404 Not Found", "text/plain; charset=utf-8", "Not found".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain; charset=utf-8", "Method not allowed".to_string()),
+    };
+
+    let mut stream = &stream;
+    write!(stream, "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}", status, content_type, body.len(), body)?;
+    stream.flush()
+}
+
+///
+/// Checks whether an address only reaches this machine or the local network, the only places the dashboard is served
+///
+pub fn is_local(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private(),
+        // Unique local addresses (fc00::/7) are IPv6's private networks
+        IpAddr::V6(ip) => ip.is_loopback() || (ip.segments()[0] & 0xfe00) == 0xfc00,
+    }
+}
+
+///
+/// Finds this machine's address on the local network, from the interface it would use to reach other networks.
+/// Connecting a UDP socket only picks the route, so nothing is sent
+///
+pub fn lan_ip() -> Result<IpAddr, String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|e| format!("Couldn't find this computer's local network address: {}", e))?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 80)).map_err(|e| format!("Couldn't find this computer's local network address: {}", e))?;
+    let ip = socket.local_addr().map_err(|e| format!("Couldn't find this computer's local network address: {}", e))?.ip();
+    if !is_local(&ip) || ip.is_loopback() {
+        return Err(format!("This computer's address {} isn't on a private network, so the dashboard won't be served on it", ip));
+    }
+    Ok(ip)
+}
+
+///
+/// Address to bind to: the one given if it's on this machine or a private network, this machine's local network
+/// address with lan, or this machine only otherwise
+///
+pub fn address(bind: Option<&str>, lan: bool, port: u16) -> Result<SocketAddr, String> {
+    let ip = match bind {
+        Some(bind) => {
+            let ip: IpAddr = bind.parse().map_err(|_| format!("{} isn't an IP address", bind))?;
+            if !is_local(&ip) {
+                return Err(format!("{} isn't on this computer or a private network, so the dashboard won't be served on it", ip));
+            }
+            ip
+        },
+        None if lan => lan_ip()?,
+        None => IpAddr::V4(Ipv4Addr::LOCALHOST),
+    };
+    Ok(SocketAddr::new(ip, port))
+}
+
+///
+/// Serves the dashboard until the process exits, handling each connection on its own thread
+///
+pub fn serve(listener: TcpListener, hp_mode: HpMode) {
+    for stream in listener.incoming().flatten() {
+        thread::spawn(move || {
+            let _ = handle_connection(stream, hp_mode);
+        });
+    }
+}
+
+///
+/// Starts the dashboard in the background so the tracker can keep running, returning an error if it's already running or can't bind
+///
+pub fn start(address: SocketAddr, hp_mode: HpMode) -> io::Result<()> {
+    if RUNNING.load(Ordering::SeqCst) {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "the dashboard is already running"));
+    }
+    let listener = TcpListener::bind(address)?;
+    RUNNING.store(true, Ordering::SeqCst);
+    thread::spawn(move || serve(listener, hp_mode));
+    Ok(())
+}
+
+///
+/// Asks how to serve the dashboard, then starts it in the background
+///
+pub fn start_dashboard() {
+    println!("Serve the player dashboard to (l)ocalhost only, or the local (n)etwork so other devices can see it?");
+    let lan = user_input::input() == "n";
+    println!("\nShow monster health as (b)ands like \"Bloodied\", or keep it (h)idden?");
+    let hp_mode = if user_input::input() == "h" {HpMode::Hidden} else {HpMode::Bands};
+    let address = match address(None, lan, DEFAULT_PORT) {
+        Ok(address) => address,
+        Err(e) => {
+            println!("\n{}\n", e);
+            return;
+        }
+    };
+    match start(address, hp_mode) {
+        Ok(..) if lan => println!("\nDashboard running. Open http://{} on the other screen\n", address),
+        Ok(..) => println!("\nDashboard running at http://localhost:{}\n", DEFAULT_PORT),
+        Err(e) => println!("\nCouldn't start the dashboard: {}\n", e),
+    }
+}